@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sixel_tokenizer::decoder::decode;
+use sixel_tokenizer::{Dithering, Encoder};
+
+mod corpus;
+
+fn codec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("codec");
+    for corpus in corpus::all() {
+        group.throughput(Throughput::Bytes(corpus.sixel.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("decode", corpus.name),
+            &corpus.sixel,
+            |b, sixel| b.iter(|| decode(black_box(sixel))),
+        );
+        let image = decode(&corpus.sixel).remove(0);
+        for (name, dithering) in [
+            ("encode", Dithering::None),
+            ("encode_dithered", Dithering::FloydSteinberg),
+        ] {
+            // the photo and many_colors corpora need their palettes reduced to fit
+            let encoder = Encoder {
+                max_colors: 64,
+                dithering,
+                ..Encoder::new()
+            };
+            group.bench_with_input(BenchmarkId::new(name, corpus.name), &image, |b, image| {
+                b.iter(|| {
+                    let mut sixel = vec![];
+                    encoder.encode_to(black_box(image), &mut sixel).unwrap();
+                    sixel
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, codec);
+criterion_main!(benches);
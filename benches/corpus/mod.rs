@@ -0,0 +1,154 @@
+//! Generated sixel images used by the benchmarks.
+//!
+//! The images are generated rather than checked in, from a simple LCG so that every run measures
+//! the same bytes.
+
+pub struct Corpus {
+    pub name: &'static str,
+    pub sixel: Vec<u8>,
+}
+
+pub fn all() -> Vec<Corpus> {
+    vec![
+        Corpus {
+            name: "photo",
+            sixel: photo(800, 480),
+        },
+        Corpus {
+            name: "line_art",
+            sixel: line_art(800, 480),
+        },
+        Corpus {
+            name: "repeated_runs",
+            sixel: repeated_runs(1600, 480),
+        },
+        Corpus {
+            name: "many_colors",
+            sixel: many_colors(800, 480),
+        },
+    ]
+}
+
+struct Lcg(u32);
+
+impl Lcg {
+    fn next(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        self.0 >> 16
+    }
+}
+
+// smooth gradients with noise over a 6x6x6 color cube, like a dithered photo
+fn photo(width: usize, height: usize) -> Vec<u8> {
+    let palette: Vec<(usize, usize, usize)> = (0..216)
+        .map(|i| (i / 36 * 20, i / 6 % 6 * 20, i % 6 * 20))
+        .collect();
+    let mut lcg = Lcg(1);
+    let pixels: Vec<usize> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let noise = lcg.next() as usize % 2;
+            let r = (x * 5 / width + noise).min(5);
+            let g = (y * 5 / height + noise).min(5);
+            let b = ((x + y) * 5 / (width + height) + noise).min(5);
+            r * 36 + g * 6 + b
+        })
+        .collect();
+    encode(width, height, &palette, &pixels)
+}
+
+// a few thin lines and circles on a blank background
+fn line_art(width: usize, height: usize) -> Vec<u8> {
+    let palette = [(100, 100, 100), (0, 0, 0), (80, 0, 0)];
+    let (cx, cy) = (width as isize / 2, height as isize / 2);
+    let pixels: Vec<usize> = (0..width * height)
+        .map(|i| {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            let distance = ((x - cx).pow(2) + (y - cy).pow(2)) as f64;
+            if (distance.sqrt() as isize) % 60 == 0 {
+                2
+            } else if x % 100 == 0 || y % 80 == 0 || x == y {
+                1
+            } else {
+                0
+            }
+        })
+        .collect();
+    encode(width, height, &palette, &pixels)
+}
+
+// wide flat stripes, which encode to a handful of repeats per band
+fn repeated_runs(width: usize, height: usize) -> Vec<u8> {
+    let palette = [(100, 0, 0), (0, 100, 0), (0, 0, 100), (100, 100, 0)];
+    let pixels: Vec<usize> = (0..width * height)
+        .map(|i| ((i % width) / 200 + (i / width) / 120) % palette.len())
+        .collect();
+    encode(width, height, &palette, &pixels)
+}
+
+// a palette of 4096 colors in which neighbouring pixels rarely share a color
+fn many_colors(width: usize, height: usize) -> Vec<u8> {
+    let palette: Vec<(usize, usize, usize)> = (0..4096)
+        .map(|i| (i / 256 * 6, i / 16 % 16 * 6, i % 16 * 6))
+        .collect();
+    let mut lcg = Lcg(7);
+    let pixels: Vec<usize> = (0..width * height)
+        .map(|i| (i % width / 3 + i / width * 7 + lcg.next() as usize % 16) % palette.len())
+        .collect();
+    encode(width, height, &palette, &pixels)
+}
+
+// a straightforward encoder: one pass per color used in each band, with repeats for long runs
+fn encode(
+    width: usize,
+    height: usize,
+    palette: &[(usize, usize, usize)],
+    pixels: &[usize],
+) -> Vec<u8> {
+    let mut sixel = format!("\x1bPq\"1;1;{};{}", width, height).into_bytes();
+    for (i, (r, g, b)) in palette.iter().enumerate() {
+        sixel.extend_from_slice(format!("#{};2;{};{};{}", i, r, g, b).as_bytes());
+    }
+    let mut columns = vec![0u8; width];
+    for band_start in (0..height).step_by(6) {
+        let band_rows = band_start..std::cmp::min(band_start + 6, height);
+        let mut colors: Vec<usize> = band_rows
+            .clone()
+            .flat_map(|y| pixels[y * width..(y + 1) * width].iter().copied())
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (pass, color) in colors.into_iter().enumerate() {
+            columns.fill(0);
+            for y in band_rows.clone() {
+                for (x, column) in columns.iter_mut().enumerate() {
+                    if pixels[y * width + x] == color {
+                        *column |= 1 << (y - band_start);
+                    }
+                }
+            }
+            if pass > 0 {
+                sixel.push(b'$');
+            }
+            sixel.extend_from_slice(format!("#{}", color).as_bytes());
+            let mut x = 0;
+            while x < width {
+                let run = columns[x..]
+                    .iter()
+                    .take_while(|c| **c == columns[x])
+                    .count();
+                let byte = b'?' + columns[x];
+                if run > 3 {
+                    sixel.extend_from_slice(format!("!{}", run).as_bytes());
+                    sixel.push(byte);
+                } else {
+                    sixel.extend(std::iter::repeat_n(byte, run));
+                }
+                x += run;
+            }
+        }
+        sixel.push(b'-');
+    }
+    sixel.extend_from_slice(b"\x1b\\");
+    sixel
+}
@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sixel_tokenizer::{Parser, SixelEvent, SliceEvent};
+
+mod corpus;
+
+fn tokenize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize");
+    for corpus in corpus::all() {
+        group.throughput(Throughput::Bytes(corpus.sixel.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("advance", corpus.name),
+            &corpus.sixel,
+            |b, sixel| {
+                b.iter(|| {
+                    let mut parser = Parser::new();
+                    let mut events = 0;
+                    for byte in black_box(sixel) {
+                        parser.advance(byte, |_: SixelEvent| events += 1);
+                    }
+                    events
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("advance_slice", corpus.name),
+            &corpus.sixel,
+            |b, sixel| {
+                b.iter(|| {
+                    let mut parser = Parser::new();
+                    let mut events = 0;
+                    parser.advance_slice(black_box(sixel), |_: SliceEvent| events += 1);
+                    events
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tokenize);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+//! Decodes arbitrary bytes with small limits.
+//!
+//! Besides not panicking, this checks that every decoded image stays within the decoder's limits
+//! and has exactly one RGBA pixel per position.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sixel_tokenizer::decoder::decode_with;
+use sixel_tokenizer::{Decoder, Limits};
+
+const LIMITS: Limits = Limits {
+    max_width: 64,
+    max_height: 64,
+    color_registers: 16,
+};
+
+fuzz_target!(|data: &[u8]| {
+    decode_with(Decoder::with_limits(LIMITS), data, |image| {
+        assert!(image.width <= LIMITS.max_width);
+        assert!(image.height <= LIMITS.max_height);
+        assert_eq!(image.pixels.len(), image.width * image.height * 4);
+    });
+});
@@ -0,0 +1,56 @@
+//! Feeds arbitrary bytes to the parser, one byte at a time and as slices.
+//!
+//! Besides not panicking, this checks that:
+//! * a single byte never produces an unbounded number of events (the parser's buffers are fixed
+//!   size, so neither should its output be)
+//! * the bytes of every `UnknownSequence` event are exactly the input bytes it covers, so that
+//!   the rejected input can be reconstructed from the events
+//! * `advance_slice` produces the same events as `advance`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sixel_tokenizer::{Parser, SixelEvent, SliceEvent, SpannedParser};
+
+// a byte can flush a full instruction buffer (256 bytes) as unknown sequences of 5 bytes each
+const MAX_EVENTS_PER_BYTE: usize = 256 / 5 + 3;
+
+fuzz_target!(|data: &[u8]| {
+    // whitespace is ignored by the parser, so it would not show up in unknown sequences
+    let input: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|byte| !matches!(byte, b' ' | b'\n' | b'\t'))
+        .collect();
+
+    let mut events = vec![];
+    let mut parser = SpannedParser::new();
+    for byte in &input {
+        let events_before = events.len();
+        parser.advance(byte, |event, span| events.push((event, span)));
+        assert!(events.len() - events_before <= MAX_EVENTS_PER_BYTE);
+    }
+    parser.finish(|event, span| events.push((event, span)));
+    for (event, span) in &events {
+        if let SixelEvent::UnknownSequence(bytes) = event {
+            let bytes: Vec<u8> = bytes.iter().flatten().copied().collect();
+            assert_eq!(
+                &input[span.clone()],
+                &bytes[..],
+                "unknown sequence at {:?}",
+                span
+            );
+        }
+    }
+
+    let mut slice_events = vec![];
+    let mut parser = Parser::new();
+    parser.advance_slice(data, |event| match event {
+        SliceEvent::DataRun(bytes) => {
+            slice_events.extend(bytes.iter().map(|byte| SixelEvent::Data { byte: *byte }))
+        }
+        SliceEvent::Event(event) => slice_events.push(event),
+    });
+    parser.finish(|event| slice_events.push(event));
+    let events: Vec<SixelEvent> = events.into_iter().map(|(event, _)| event).collect();
+    assert_eq!(events, slice_events);
+});
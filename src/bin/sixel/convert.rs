@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+
+use png::{BitDepth, ColorType, Transformations};
+use sixel_tokenizer::decoder::decode_with;
+use sixel_tokenizer::{Bitmap, Decoder, Encoder, RegisterMode};
+
+/// How sixel images are turned into PNGs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecodeOptions {
+    /// The color of unpainted pixels, which are otherwise black or (if the image asks for it)
+    /// transparent.
+    pub background: Option<[u8; 3]>,
+    /// Stretch the image so that its pixels are square.
+    pub correct_aspect_ratio: bool,
+    /// Which of the images in the input to convert, counting from 0.
+    pub index: usize,
+    /// Whether the images before it may have defined its colors.
+    pub register_mode: RegisterMode,
+}
+
+/// Writes one of the sixel images in the input as a PNG.
+pub fn sixel_to_png(input: &[u8], options: &DecodeOptions, out: &mut impl Write) -> io::Result<()> {
+    let mut decoder = Decoder::new();
+    decoder.set_register_mode(options.register_mode);
+    if let Some([r, g, b]) = options.background {
+        decoder.set_background([r, g, b, 255]);
+    }
+    let limits = decoder.limits();
+    let mut images = 0;
+    let mut selected = None;
+    decode_with(decoder, input, |image| {
+        if images == options.index {
+            selected = Some(image);
+        }
+        images += 1;
+    });
+    let mut image = selected.ok_or_else(|| {
+        let message = match images {
+            0 => "no sixel image in the input".to_owned(),
+            1 => format!("no image {}, the input has 1 image", options.index),
+            _ => format!(
+                "no image {}, the input has {} images",
+                options.index, images
+            ),
+        };
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    })?;
+    if image.width == 0 || image.height == 0 {
+        // PNG has no empty images
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "image {} is empty ({}x{} pixels)",
+                options.index, image.width, image.height
+            ),
+        ));
+    }
+    if let Some(background) = options.background {
+        image.flatten(background);
+    }
+    if options.correct_aspect_ratio {
+        image = image.correct_aspect_ratio(&limits).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the image is too large once its aspect ratio is corrected",
+            )
+        })?;
+    }
+    write_png(&image, out)
+}
+
+/// Writes a PNG as a sixel image.
+pub fn png_to_sixel(input: &[u8], encoder: &Encoder, out: &mut impl Write) -> io::Result<()> {
+    encoder.encode_to(&read_png(input)?, out)
+}
+
+pub fn read_png(input: &[u8]) -> io::Result<Bitmap> {
+    let mut decoder = png::Decoder::new(input);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(invalid_png)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(invalid_png)?;
+    let buffer = &buffer[..frame.buffer_size()];
+    let pixels = match frame.color_type {
+        ColorType::Rgba => buffer.to_vec(),
+        ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        ColorType::Grayscale => buffer.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        // expanded to RGB(A) by the transformations
+        ColorType::Indexed => unreachable!(),
+    };
+    Ok(Bitmap::from_rgba(
+        frame.width as usize,
+        frame.height as usize,
+        pixels,
+    ))
+}
+
+pub fn write_png(image: &Bitmap, out: &mut impl Write) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(out, image.width as u32, image.height as u32);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels)?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn invalid_png(e: png::DecodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid PNG: {}", e))
+}
+
+/// Parses a color given as `RRGGBB` or `#RRGGBB`.
+pub fn parse_color(color: &str) -> Result<[u8; 3], String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("expected a color like `ff8000`, got `{}`", color));
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+#[cfg(test)]
+#[path = "./convert_tests.rs"]
+mod tests;
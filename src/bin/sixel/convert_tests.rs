@@ -0,0 +1,94 @@
+use super::*;
+
+const SIXEL: &[u8] = b"\x1bP0;1q\"2;1;3;6#1;2;100;0;0~~@\x1b\\";
+
+fn to_png(sixel: &[u8], options: DecodeOptions) -> Bitmap {
+    let mut png = vec![];
+    sixel_to_png(sixel, &options, &mut png).unwrap();
+    read_png(&png).unwrap()
+}
+
+#[test]
+fn sixel_to_png_keeps_transparency() {
+    let image = to_png(SIXEL, DecodeOptions::default());
+    assert_eq!((image.width, image.height), (3, 6));
+    assert_eq!(image.pixel(1, 5), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(2, 5), [0, 0, 0, 0]);
+}
+
+#[test]
+fn sixel_to_png_with_background_and_aspect_ratio() {
+    let options = DecodeOptions {
+        background: Some([0, 0, 255]),
+        correct_aspect_ratio: true,
+        ..DecodeOptions::default()
+    };
+    let image = to_png(SIXEL, options);
+    assert_eq!((image.width, image.height), (3, 12));
+    assert_eq!(image.pixel(2, 0), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(2, 11), [0, 0, 255, 255]);
+}
+
+#[test]
+fn sixel_to_png_selects_an_image() {
+    let input = [SIXEL, b"\x1bPq#2@\x1b\\"].concat();
+    let options = DecodeOptions {
+        index: 1,
+        ..DecodeOptions::default()
+    };
+    assert_eq!(to_png(&input, options).pixel(0, 0), [204, 33, 33, 255]);
+    let options = DecodeOptions {
+        index: 2,
+        ..DecodeOptions::default()
+    };
+    let error = sixel_to_png(&input, &options, &mut vec![]).unwrap_err();
+    assert_eq!(error.to_string(), "no image 2, the input has 2 images");
+}
+
+#[test]
+fn sixel_to_png_rejects_empty_images() {
+    let input = [b"\x1bPq\x1b\\".as_slice(), b"\x1bPq\"1;1;0;6\x1b\\"].concat();
+    let error = sixel_to_png(&input, &DecodeOptions::default(), &mut vec![]).unwrap_err();
+    assert_eq!(error.to_string(), "image 0 is empty (0x0 pixels)");
+    let options = DecodeOptions {
+        index: 1,
+        ..DecodeOptions::default()
+    };
+    let error = sixel_to_png(&input, &options, &mut vec![]).unwrap_err();
+    assert_eq!(error.to_string(), "image 1 is empty (0x6 pixels)");
+}
+
+#[test]
+fn png_round_trip() {
+    let image = to_png(SIXEL, DecodeOptions::default());
+    let mut png = vec![];
+    write_png(&image, &mut png).unwrap();
+    let mut sixel = vec![];
+    png_to_sixel(&png, &Encoder::new(), &mut sixel).unwrap();
+    assert_eq!(to_png(&sixel, DecodeOptions::default()), image);
+}
+
+#[test]
+fn png_to_sixel_rejects_other_input() {
+    let error = png_to_sixel(SIXEL, &Encoder::new(), &mut vec![]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn colors() {
+    assert_eq!(parse_color("ff8000"), Ok([255, 128, 0]));
+    assert_eq!(parse_color("#0A0b0c"), Ok([10, 11, 12]));
+    assert!(parse_color("fff").is_err());
+    assert!(parse_color("+f+f+f").is_err());
+}
+
+#[test]
+fn sixel_to_png_with_shared_registers() {
+    let input = [SIXEL, b"\x1bPq#1@\x1b\\"].concat();
+    let options = DecodeOptions {
+        index: 1,
+        register_mode: RegisterMode::Shared,
+        ..DecodeOptions::default()
+    };
+    assert_eq!(to_png(&input, options).pixel(0, 0), [255, 0, 0, 255]);
+}
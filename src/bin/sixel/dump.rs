@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+
+use sixel_tokenizer::preview::render;
+use sixel_tokenizer::{Decoder, PreviewStyle, SpannedParser};
+
+/// How `dump` previews the images it decodes.
+#[derive(Clone, Copy, Debug)]
+pub struct Preview {
+    pub columns: usize,
+    pub style: PreviewStyle,
+}
+
+/// Writes one line per event: its byte span in the input and the event itself. With a preview,
+/// each image is rendered after its last event.
+pub fn run(input: &[u8], preview: Option<Preview>, out: &mut impl Write) -> io::Result<()> {
+    let mut parser = SpannedParser::new();
+    let mut decoder = Decoder::new();
+    let mut result = Ok(());
+    let mut write_event = |event, span: std::ops::Range<usize>| {
+        if result.is_err() {
+            return;
+        }
+        let span = format!("{}..{}", span.start, span.end);
+        result = writeln!(out, "{:<14}{:?}", span, event);
+        if let Some(preview) = preview {
+            decoder.advance(event, |image| {
+                if result.is_ok() {
+                    result = write!(out, "{}", render(&image, preview.columns, preview.style));
+                }
+            });
+        }
+    };
+    for byte in input {
+        parser.advance(byte, &mut write_event);
+    }
+    parser.finish(&mut write_event);
+    result
+}
+
+#[cfg(test)]
+#[path = "./dump_tests.rs"]
+mod tests;
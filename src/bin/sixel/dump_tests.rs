@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn dump_with_offsets() {
+    let mut out = vec![];
+    run(b"\x1bPq#1;2;100;0;0~!3@\x1b\\", None, &mut out).unwrap();
+    let expected = "\
+0..3          Dcs { macro_parameter: None, transparent_background: None, horizontal_pixel_distance: None }
+3..15         ColorIntroducer { color_number: 1, color_coordinate_system: Some(RGB(100, 0, 0)) }
+15..16        Data { byte: 126 }
+16..19        Repeat { repeat_count: 3, byte_to_repeat: 64 }
+19..21        End
+";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn dump_unterminated_image() {
+    let mut out = vec![];
+    run(b"\x1bPq#1", None, &mut out).unwrap();
+    let expected = "\
+0..3          Dcs { macro_parameter: None, transparent_background: None, horizontal_pixel_distance: None }
+3..5          ColorIntroducer { color_number: 1, color_coordinate_system: None }
+5..5          Unterminated
+";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn dump_with_preview() {
+    let mut out = vec![];
+    let preview = Preview {
+        columns: 4,
+        style: PreviewStyle::Ascii,
+    };
+    let input = b"\x1bPq\"1;1;2;4#1;2;100;100;100!2B$#2;2;100;0;0!2K\x1b\\";
+    run(input, Some(preview), &mut out).unwrap();
+    let expected = "\
+0..3          Dcs { macro_parameter: None, transparent_background: None, horizontal_pixel_distance: None }
+3..11         RasterAttribute { pan: 1, pad: 1, ph: Some(2), pv: Some(4) }
+11..27        ColorIntroducer { color_number: 1, color_coordinate_system: Some(RGB(100, 100, 100)) }
+27..30        Repeat { repeat_count: 2, byte_to_repeat: 66 }
+30..31        GotoBeginningOfLine
+31..43        ColorIntroducer { color_number: 2, color_coordinate_system: Some(RGB(100, 0, 0)) }
+43..46        Repeat { repeat_count: 2, byte_to_repeat: 75 }
+46..48        End
+@@
+::
+";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use sixel_tokenizer::placement::ImageExtent;
+use sixel_tokenizer::{ColorCoordinateSystem, SixelEvent, SliceEvent, Stats};
+
+#[derive(Debug, Default)]
+struct ImageInfo {
+    transparent_background: bool,
+    aspect_ratio: Option<(usize, usize)>,
+    declared_size: Option<(Option<usize>, Option<usize>)>,
+    palette: BTreeMap<u16, ColorCoordinateSystem>,
+    extent: ImageExtent,
+    terminated: bool,
+}
+
+impl ImageInfo {
+    fn advance(&mut self, event: SixelEvent) {
+        self.extent.advance(event);
+        match event {
+            SixelEvent::Dcs {
+                transparent_background,
+                ..
+            } => self.transparent_background = transparent_background == Some(1),
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                self.aspect_ratio = Some((pan, pad));
+                self.declared_size = Some((ph, pv));
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: Some(color_coordinate_system),
+            } => {
+                self.palette.insert(color_number, color_coordinate_system);
+            }
+            SixelEvent::End => self.terminated = true,
+            _ => {}
+        }
+    }
+    fn write(&self, index: usize, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "image {}", index)?;
+        let (width, height) = self.extent.painted_size();
+        writeln!(out, "  painted size: {}x{}", width, height)?;
+        if let Some((ph, pv)) = self.declared_size {
+            let dimension = |d: Option<usize>| d.map_or("?".to_owned(), |d| d.to_string());
+            writeln!(out, "  declared size: {}x{}", dimension(ph), dimension(pv))?;
+        }
+        if let Some((pan, pad)) = self.aspect_ratio {
+            writeln!(out, "  pixel aspect ratio: {}:{}", pan, pad)?;
+        }
+        let background = if self.transparent_background {
+            "transparent"
+        } else {
+            "color 0"
+        };
+        writeln!(out, "  background: {}", background)?;
+        if !self.terminated {
+            writeln!(out, "  not terminated")?;
+        }
+        writeln!(out, "  palette: {} colors", self.palette.len())?;
+        for (color_number, color) in &self.palette {
+            match color {
+                ColorCoordinateSystem::HLS(h, l, s) => {
+                    writeln!(out, "    #{} HLS {} {} {}", color_number, h, l, s)?
+                }
+                ColorCoordinateSystem::RGB(r, g, b) => {
+                    writeln!(out, "    #{} RGB {} {} {}", color_number, r, g, b)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a summary of every image in the input, followed by statistics for the whole input.
+pub fn run(input: &[u8], out: &mut impl Write) -> io::Result<()> {
+    let mut stats = Stats::new();
+    let mut images = vec![];
+    let mut handle_event = |event: SixelEvent| {
+        stats.advance(event);
+        if let SixelEvent::Dcs { .. } = event {
+            images.push(ImageInfo::default());
+        }
+        if let Some(image) = images.last_mut() {
+            image.advance(event);
+        }
+    };
+    let mut parser = sixel_tokenizer::Parser::new();
+    parser.advance_slice(input, |event| match event {
+        SliceEvent::DataRun(bytes) => {
+            for byte in bytes {
+                handle_event(SixelEvent::Data { byte: *byte });
+            }
+        }
+        SliceEvent::Event(event) => handle_event(event),
+    });
+    parser.finish(&mut handle_event);
+    for (i, image) in images.iter().enumerate() {
+        image.write(i + 1, out)?;
+    }
+    writeln!(out, "total")?;
+    writeln!(out, "  images: {}", stats.images)?;
+    writeln!(out, "  bytes: {}", stats.bytes)?;
+    writeln!(out, "  bands: {}", stats.bands)?;
+    writeln!(out, "  colors defined: {}", stats.colors_defined)?;
+    writeln!(out, "  colors used: {}", stats.colors_used)?;
+    writeln!(out, "  painted pixels: {}", stats.painted_pixels)?;
+    writeln!(out, "  sixel columns: {}", stats.sixel_columns)?;
+    writeln!(out, "  runs: {}", stats.runs)?;
+    writeln!(
+        out,
+        "  average run length: {:.2}",
+        stats.average_run_length()
+    )?;
+    writeln!(out, "  compression ratio: {:.2}", stats.compression_ratio())?;
+    writeln!(out, "  unknown sequences: {}", stats.unknown_sequences)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./info_tests.rs"]
+mod tests;
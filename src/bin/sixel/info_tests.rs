@@ -0,0 +1,40 @@
+use super::*;
+
+#[test]
+fn image_info() {
+    let mut out = vec![];
+    let input = b"\x1bP0;1q\"2;1;10;12#0;2;0;0;0#1;1;120;50;100#1~~@@-!4N$#0A\x1b\\";
+    run(input, &mut out).unwrap();
+    let expected = "\
+image 1
+  painted size: 4x10
+  declared size: 10x12
+  pixel aspect ratio: 2:1
+  background: transparent
+  palette: 2 colors
+    #0 RGB 0 0 0
+    #1 HLS 120 50 100
+total
+  images: 1
+  bytes: 57
+  bands: 2
+  colors defined: 2
+  colors used: 2
+  painted pixels: 31
+  sixel columns: 9
+  runs: 4
+  average run length: 2.25
+  compression ratio: 1.12
+  unknown sequences: 0
+";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn unterminated_image_info() {
+    let mut out = vec![];
+    run(b"\x1bPq~", &mut out).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.starts_with("image 1\n  painted size: 1x6\n"));
+    assert!(output.contains("  not terminated\n"));
+}
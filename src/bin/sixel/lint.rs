@@ -0,0 +1,15 @@
+use std::io::{self, Write};
+
+use sixel_tokenizer::lint::lint;
+
+/// Writes the diagnostics of the input, returning false if any of them are errors.
+pub fn run(input: &[u8], out: &mut impl Write) -> io::Result<bool> {
+    let diagnostics = lint(input);
+    for diagnostic in &diagnostics {
+        writeln!(out, "{}", diagnostic)?;
+    }
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    writeln!(out, "{} errors, {} warnings", errors, warnings)?;
+    Ok(errors == 0)
+}
@@ -0,0 +1,201 @@
+//! A command-line tool for inspecting sixel images and converting them to and from PNG.
+//!
+//! Built with the `cli` feature: `cargo install sixel-tokenizer --features cli`.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use sixel_tokenizer::{Dithering, Encoder, PreviewStyle, RegisterMode};
+
+mod convert;
+mod dump;
+mod info;
+mod lint;
+
+#[derive(Parser)]
+#[command(name = "sixel", version, about = "Inspect and convert sixel images")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the events of the input along with their byte offsets
+    Dump {
+        /// The file to read, or stdin if omitted or `-`
+        input: Option<PathBuf>,
+        /// Render each image as text after its events
+        #[arg(long)]
+        preview: bool,
+        /// The largest number of columns a preview may take
+        #[arg(long, default_value_t = 80, requires = "preview")]
+        preview_columns: usize,
+        /// Preview with ASCII characters instead of colored half blocks
+        #[arg(long, requires = "preview")]
+        ascii: bool,
+    },
+    /// Print the dimensions, palette and statistics of each image in the input
+    Info {
+        /// The file to read, or stdin if omitted or `-`
+        input: Option<PathBuf>,
+    },
+    /// Report violations of the sixel spec, exiting with 1 if any of them are errors
+    Lint {
+        /// The file to read, or stdin if omitted or `-`
+        input: Option<PathBuf>,
+    },
+    /// Convert a sixel image to PNG
+    Sixel2png {
+        /// The file to read, or stdin if omitted or `-`
+        input: Option<PathBuf>,
+        /// The file to write, or stdout if omitted or `-`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The color of unpainted pixels as `RRGGBB`, replacing transparency
+        #[arg(long, value_parser = convert::parse_color)]
+        background: Option<[u8; 3]>,
+        /// Stretch the image so that its pixels are square
+        #[arg(long)]
+        correct_aspect_ratio: bool,
+        /// Which image of the input to convert, counting from 0
+        #[arg(long, default_value_t = 0)]
+        index: usize,
+        /// Let color registers keep their colors from one image to the next
+        #[arg(long)]
+        shared_registers: bool,
+    },
+    /// Convert a PNG to a sixel image
+    Png2sixel {
+        /// The file to read, or stdin if omitted or `-`
+        input: Option<PathBuf>,
+        /// The file to write, or stdout if omitted or `-`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The largest number of color registers to use
+        #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u32).range(1..=65536))]
+        colors: u32,
+        /// How to dither images with more colors than the palette
+        #[arg(long, value_enum, default_value_t = Dither::FloydSteinberg)]
+        dither: Dither,
+        /// Pixels with a lower alpha are left transparent
+        #[arg(long, default_value_t = 128, conflicts_with = "opaque")]
+        alpha_threshold: u8,
+        /// Paint every pixel, ignoring transparency
+        #[arg(long)]
+        opaque: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Dither {
+    None,
+    FloydSteinberg,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            eprintln!("sixel: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(command: Command) -> io::Result<ExitCode> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match command {
+        Command::Dump {
+            input,
+            preview,
+            preview_columns,
+            ascii,
+        } => {
+            let preview = preview.then_some(dump::Preview {
+                columns: preview_columns,
+                style: if ascii {
+                    PreviewStyle::Ascii
+                } else {
+                    PreviewStyle::HalfBlocks
+                },
+            });
+            dump::run(&read_input(input)?, preview, &mut out)?
+        }
+        Command::Info { input } => info::run(&read_input(input)?, &mut out)?,
+        Command::Lint { input } => {
+            if !lint::run(&read_input(input)?, &mut out)? {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Sixel2png {
+            input,
+            output,
+            background,
+            correct_aspect_ratio,
+            index,
+            shared_registers,
+        } => {
+            let options = convert::DecodeOptions {
+                background,
+                correct_aspect_ratio,
+                index,
+                register_mode: if shared_registers {
+                    RegisterMode::Shared
+                } else {
+                    RegisterMode::Private
+                },
+            };
+            let mut png = vec![];
+            convert::sixel_to_png(&read_input(input)?, &options, &mut png)?;
+            write_output(output, &png, &mut out)?;
+        }
+        Command::Png2sixel {
+            input,
+            output,
+            colors,
+            dither,
+            alpha_threshold,
+            opaque,
+        } => {
+            let encoder = Encoder {
+                max_colors: colors as usize,
+                dithering: match dither {
+                    Dither::None => Dithering::None,
+                    Dither::FloydSteinberg => Dithering::FloydSteinberg,
+                },
+                alpha_threshold: if opaque { None } else { Some(alpha_threshold) },
+            };
+            let mut sixel = vec![];
+            convert::png_to_sixel(&read_input(input)?, &encoder, &mut sixel)?;
+            write_output(output, &sixel, &mut out)?;
+        }
+    }
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read_input(input: Option<PathBuf>) -> io::Result<Vec<u8>> {
+    match input {
+        Some(path) if path.as_os_str() != "-" => fs::read(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        _ => {
+            let mut bytes = vec![];
+            io::stdin().lock().read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+fn write_output(output: Option<PathBuf>, bytes: &[u8], stdout: &mut impl Write) -> io::Result<()> {
+    match output {
+        Some(path) if path.as_os_str() != "-" => fs::write(&path, bytes)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        _ => stdout.write_all(bytes),
+    }
+}
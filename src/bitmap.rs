@@ -0,0 +1,84 @@
+use crate::Limits;
+
+/// An RGBA image, as produced by the [`Decoder`](crate::Decoder) and consumed by the
+/// [`Encoder`](crate::Encoder).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major RGBA pixels, 4 bytes each. Transparent pixels have an alpha of 0.
+    pub pixels: Vec<u8>,
+    /// The pixel aspect ratio as `(pan, pad)`: pixels are `pan / pad` times as tall as they are
+    /// wide.
+    pub pixel_aspect_ratio: (usize, usize),
+}
+
+impl Bitmap {
+    /// A transparent bitmap with square pixels.
+    pub fn new(width: usize, height: usize) -> Self {
+        Bitmap::from_rgba(width, height, vec![0; width * height * 4])
+    }
+    /// A bitmap with square pixels. `pixels` must hold `width * height` RGBA pixels.
+    pub fn from_rgba(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width * height * 4, "wrong number of pixels");
+        Bitmap {
+            width,
+            height,
+            pixels,
+            pixel_aspect_ratio: (1, 1),
+        }
+    }
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        let i = (y * self.width + x) * 4;
+        self.pixels[i..i + 4].copy_from_slice(&color);
+    }
+    /// Stretches the bitmap vertically so that its pixels are square. Returns `None` if the
+    /// stretched bitmap would be taller than `limits.max_height` or have more pixels than
+    /// `limits.max_width * limits.max_height`, since the pixel aspect ratio comes from the image
+    /// and can be as large as its raster attributes allow.
+    pub fn correct_aspect_ratio(&self, limits: &Limits) -> Option<Bitmap> {
+        let (pan, pad) = self.pixel_aspect_ratio;
+        if pan == pad || pan == 0 || pad == 0 {
+            return Some(Bitmap {
+                pixel_aspect_ratio: (1, 1),
+                ..self.clone()
+            });
+        }
+        let height = self.height.checked_mul(pan)?.checked_add(pad / 2)? / pad;
+        let max_pixels = limits.max_width.saturating_mul(limits.max_height);
+        if height > limits.max_height || self.width.checked_mul(height)? > max_pixels {
+            return None;
+        }
+        let row_len = self.width * 4;
+        let mut pixels = Vec::with_capacity(height * row_len);
+        for y in 0..height {
+            let source_y = std::cmp::min(y * pad / pan, self.height - 1);
+            pixels.extend_from_slice(&self.pixels[source_y * row_len..(source_y + 1) * row_len]);
+        }
+        Some(Bitmap::from_rgba(self.width, height, pixels))
+    }
+    /// Composites the bitmap over an opaque background color, leaving no transparent pixels.
+    pub fn flatten(&mut self, background: [u8; 3]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for (channel, background) in pixel.iter_mut().zip(background) {
+                *channel = ((*channel as u32 * alpha + background as u32 * (255 - alpha) + 127)
+                    / 255) as u8;
+            }
+            pixel[3] = 255;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./bitmap_tests.rs"]
+mod tests;
@@ -0,0 +1,68 @@
+use super::*;
+
+fn column(colors: &[[u8; 4]]) -> Bitmap {
+    Bitmap::from_rgba(1, colors.len(), colors.concat())
+}
+
+#[test]
+fn stretch_tall_pixels() {
+    let mut bitmap = column(&[[1, 1, 1, 255], [2, 2, 2, 255]]);
+    bitmap.pixel_aspect_ratio = (2, 1);
+    let corrected = bitmap.correct_aspect_ratio(&Limits::default()).unwrap();
+    assert_eq!(corrected.height, 4);
+    assert_eq!(corrected.pixel_aspect_ratio, (1, 1));
+    let rows: Vec<u8> = (0..4).map(|y| corrected.pixel(0, y)[0]).collect();
+    assert_eq!(rows, vec![1, 1, 2, 2]);
+}
+
+#[test]
+fn squash_wide_pixels() {
+    let mut bitmap = column(&[
+        [1, 1, 1, 255],
+        [2, 2, 2, 255],
+        [3, 3, 3, 255],
+        [4, 4, 4, 255],
+    ]);
+    bitmap.pixel_aspect_ratio = (1, 2);
+    let corrected = bitmap.correct_aspect_ratio(&Limits::default()).unwrap();
+    let rows: Vec<u8> = (0..corrected.height)
+        .map(|y| corrected.pixel(0, y)[0])
+        .collect();
+    assert_eq!(rows, vec![1, 3]);
+}
+
+#[test]
+fn square_pixels_are_unchanged() {
+    let mut bitmap = column(&[[1, 1, 1, 255], [2, 2, 2, 255]]);
+    bitmap.pixel_aspect_ratio = (3, 3);
+    let corrected = bitmap.correct_aspect_ratio(&Limits::default()).unwrap();
+    assert_eq!(corrected.pixels, bitmap.pixels);
+    assert_eq!(corrected.pixel_aspect_ratio, (1, 1));
+}
+
+#[test]
+fn correction_is_bounded_by_the_limits() {
+    let mut bitmap = column(&[[1, 1, 1, 255], [2, 2, 2, 255]]);
+    bitmap.pixel_aspect_ratio = (99999, 1);
+    assert_eq!(bitmap.correct_aspect_ratio(&Limits::default()), None);
+    bitmap.pixel_aspect_ratio = (usize::MAX, 1);
+    assert_eq!(bitmap.correct_aspect_ratio(&Limits::default()), None);
+    let limits = Limits {
+        max_width: 2,
+        max_height: 4,
+        color_registers: 16,
+    };
+    bitmap.pixel_aspect_ratio = (2, 1);
+    assert_eq!(bitmap.correct_aspect_ratio(&limits).unwrap().height, 4);
+    bitmap.pixel_aspect_ratio = (3, 1);
+    assert_eq!(bitmap.correct_aspect_ratio(&limits), None);
+}
+
+#[test]
+fn flatten_onto_background() {
+    let mut bitmap = column(&[[0, 0, 0, 0], [255, 0, 0, 255], [255, 255, 255, 128]]);
+    bitmap.flatten([0, 0, 200]);
+    assert_eq!(bitmap.pixel(0, 0), [0, 0, 200, 255]);
+    assert_eq!(bitmap.pixel(0, 1), [255, 0, 0, 255]);
+    assert_eq!(bitmap.pixel(0, 2), [128, 128, 228, 255]);
+}
@@ -0,0 +1,395 @@
+//! A [`SixelEvent`] stream transformer that clips images to a rectangle.
+//!
+//! Coordinates are in sixel pixels, before the pixel aspect ratio is applied. Since a band of sixel
+//! data is six pixels high, a crop that does not start on a band boundary moves the pixels of
+//! every input band into two output bands. To do this, [`Crop`] buffers the color passes of one
+//! band at a time and re-emits them with their sixel bits masked and shifted. Color registers
+//! that a band redefines are defined again where needed, so that each pass paints with the colors
+//! it had in the input.
+//!
+//! Raster attributes are rewritten to the size of the cropped image and `Repeat` events are kept
+//! (with their counts clipped) where possible. Unknown sequences inside of images are dropped.
+
+use std::collections::BTreeMap;
+
+use crate::palette::vt340_color;
+use crate::{ColorCoordinateSystem, SixelEvent};
+
+const BLANK: u8 = b'?';
+
+#[derive(Clone, Debug)]
+pub struct Crop {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    in_image: bool,
+    current_color: Option<u16>,
+    column: usize,
+    band: usize,
+    pass: Pass,
+    current_band: Vec<BandItem>,
+    previous_band: Option<Vec<BandItem>>,
+    // the color registers as they were defined before the previous band
+    previous_band_registers: BTreeMap<u16, ColorCoordinateSystem>,
+    emitted_color: Option<u16>,
+    output_band: usize,
+    output_band_has_pass: bool,
+}
+
+#[derive(Clone, Debug)]
+enum BandItem {
+    ColorDefinition(SixelEvent),
+    Pass(Pass),
+}
+
+#[derive(Clone, Debug, Default)]
+struct Pass {
+    color: Option<u16>,
+    // the output column this pass starts at
+    start: usize,
+    runs: Vec<Run>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    byte: u8,
+    count: usize,
+    is_repeat: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BandPart {
+    // the rows of an input band that fall into the output band before it
+    Top,
+    // the rows of an input band that fall into the output band with its index
+    Bottom,
+}
+
+impl Crop {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Crop {
+            x,
+            y,
+            width,
+            height,
+            in_image: false,
+            current_color: None,
+            column: 0,
+            band: 0,
+            pass: Pass::default(),
+            current_band: vec![],
+            previous_band: None,
+            previous_band_registers: BTreeMap::new(),
+            emitted_color: None,
+            output_band: 0,
+            output_band_has_pass: false,
+        }
+    }
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(SixelEvent)) {
+        if !self.in_image {
+            if let SixelEvent::Dcs { .. } = event {
+                self.start_image();
+            }
+            cb(event);
+            return;
+        }
+        match event {
+            SixelEvent::Dcs { .. } => {
+                self.finish_image(&mut cb);
+                self.start_image();
+                cb(event);
+            }
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                cb(SixelEvent::RasterAttribute {
+                    pan,
+                    pad,
+                    ph: ph.map(|ph| std::cmp::min(ph.saturating_sub(self.x), self.width)),
+                    pv: pv.map(|pv| std::cmp::min(pv.saturating_sub(self.y), self.height)),
+                });
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system,
+            } => {
+                self.finish_pass();
+                if color_coordinate_system.is_some() {
+                    self.current_band.push(BandItem::ColorDefinition(event));
+                }
+                self.current_color = Some(color_number);
+                self.pass.color = self.current_color;
+            }
+            SixelEvent::Data { byte } => self.push_run(byte, 1, false),
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => self.push_run(byte_to_repeat, repeat_count, true),
+            SixelEvent::GotoBeginningOfLine => {
+                self.finish_pass();
+                self.column = 0;
+                self.pass.start = 0;
+            }
+            SixelEvent::GotoNextLine => {
+                self.finish_band(&mut cb);
+                self.band += 1;
+            }
+            SixelEvent::End | SixelEvent::Unterminated => {
+                self.finish_image(&mut cb);
+                cb(event);
+            }
+            SixelEvent::UnknownSequence(_) => {}
+        }
+    }
+    /// Flushes an image that was not terminated by an `End` event.
+    pub fn finish(&mut self, mut cb: impl FnMut(SixelEvent)) {
+        if self.in_image {
+            self.finish_image(&mut cb);
+        }
+    }
+    fn start_image(&mut self) {
+        *self = Crop::new(self.x, self.y, self.width, self.height);
+        self.in_image = true;
+    }
+    fn finish_image(&mut self, cb: &mut impl FnMut(SixelEvent)) {
+        self.finish_band(cb);
+        if let Some(previous_band) = self.previous_band.take() {
+            self.emit_output_band(
+                self.band as isize - (self.y / 6) as isize,
+                &previous_band,
+                &[],
+                cb,
+            );
+        }
+        self.in_image = false;
+    }
+    fn push_run(&mut self, byte: u8, count: usize, is_repeat: bool) {
+        let first_column = std::cmp::max(self.column, self.x);
+        let last_column = std::cmp::min(self.column + count, self.x + self.width);
+        self.column += count;
+        if first_column < last_column {
+            self.pass.runs.push(Run {
+                byte,
+                count: last_column - first_column,
+                is_repeat,
+            });
+        }
+    }
+    fn finish_pass(&mut self) {
+        let output_column = self.column.saturating_sub(self.x);
+        let next_pass = Pass {
+            color: self.current_color,
+            start: std::cmp::min(output_column, self.width),
+            runs: vec![],
+        };
+        let pass = std::mem::replace(&mut self.pass, next_pass);
+        if !pass.runs.is_empty() {
+            self.current_band.push(BandItem::Pass(pass));
+        }
+    }
+    fn finish_band(&mut self, cb: &mut impl FnMut(SixelEvent)) {
+        self.finish_pass();
+        self.column = 0;
+        self.pass.start = 0;
+        let current_band = std::mem::take(&mut self.current_band);
+        let previous_band = self.previous_band.take().unwrap_or_default();
+        // the bottom rows of the previous band and the top rows of this one make up an output band
+        let output_band = self.band as isize - (self.y / 6) as isize - 1;
+        self.emit_output_band(output_band, &previous_band, &current_band, cb);
+        self.previous_band = Some(current_band);
+    }
+    fn emit_output_band(
+        &mut self,
+        output_band: isize,
+        bottom_of: &[BandItem],
+        top_of: &[BandItem],
+        cb: &mut impl FnMut(SixelEvent),
+    ) {
+        let output_bands = self.height.div_ceil(6);
+        let in_crop = output_band >= 0 && (output_band as usize) < output_bands;
+        let is_aligned = self.y.is_multiple_of(6);
+        // Every pass has to paint with the colors its registers had at that point of the input.
+        // Unless the crop is aligned to bands, the definitions of a band are emitted with its top
+        // rows, so its bottom rows start out with the registers as they are at the end of the
+        // band. Registers are then defined again as the bottom rows' passes need them.
+        let replay = !is_aligned && in_crop;
+        let mut band_end = std::mem::take(&mut self.previous_band_registers);
+        let mut registers = if replay {
+            band_end.clone()
+        } else {
+            BTreeMap::new()
+        };
+        for item in bottom_of {
+            if let BandItem::ColorDefinition(SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: Some(color),
+            }) = item
+            {
+                band_end.insert(*color_number, *color);
+            }
+        }
+        let mut emitted_registers = if replay {
+            band_end.clone()
+        } else {
+            BTreeMap::new()
+        };
+        let parts = [(BandPart::Bottom, bottom_of), (BandPart::Top, top_of)];
+        for (part, items) in parts {
+            for item in items {
+                match (part, item) {
+                    (
+                        BandPart::Bottom,
+                        BandItem::ColorDefinition(SixelEvent::ColorIntroducer {
+                            color_number,
+                            color_coordinate_system: Some(color),
+                        }),
+                    ) => {
+                        if is_aligned {
+                            self.emit_color_definition(*color_number, *color, cb);
+                        }
+                        if replay {
+                            registers.insert(*color_number, *color);
+                        }
+                    }
+                    (
+                        BandPart::Top,
+                        BandItem::ColorDefinition(SixelEvent::ColorIntroducer {
+                            color_number,
+                            color_coordinate_system: Some(color),
+                        }),
+                    ) if !is_aligned => {
+                        self.emit_color_definition(*color_number, *color, cb);
+                    }
+                    (_, BandItem::ColorDefinition(_)) => {}
+                    (_, BandItem::Pass(pass)) if in_crop => {
+                        if let (BandPart::Bottom, true) = (part, replay) {
+                            let register = pass.color.unwrap_or(0);
+                            let color = register_color(&registers, register);
+                            if register_color(&emitted_registers, register) != color {
+                                self.emit_color_definition(register, color, cb);
+                                emitted_registers.insert(register, color);
+                            }
+                        }
+                        self.emit_pass(pass, part, output_band as usize, cb);
+                    }
+                    (_, BandItem::Pass(_)) => {}
+                }
+            }
+            if let (BandPart::Bottom, true) = (part, replay) {
+                // the next band starts out with the registers as they are at the end of this one
+                for (register, color) in &band_end {
+                    if emitted_registers.get(register) != Some(color) {
+                        self.emit_color_definition(*register, *color, cb);
+                    }
+                }
+            }
+        }
+        self.previous_band_registers = band_end;
+    }
+    fn emit_color_definition(
+        &mut self,
+        color_number: u16,
+        color: ColorCoordinateSystem,
+        cb: &mut impl FnMut(SixelEvent),
+    ) {
+        self.emitted_color = Some(color_number);
+        cb(SixelEvent::ColorIntroducer {
+            color_number,
+            color_coordinate_system: Some(color),
+        });
+    }
+    fn row_mask(&self, output_band: usize) -> u8 {
+        let rows = std::cmp::min(6, self.height - output_band * 6);
+        (1 << rows) - 1
+    }
+    fn shift(&self, bits: u8, part: BandPart) -> u8 {
+        let offset = self.y % 6;
+        match part {
+            BandPart::Bottom => bits >> offset,
+            BandPart::Top if offset == 0 => 0,
+            BandPart::Top => (bits << (6 - offset)) & 0b111111,
+        }
+    }
+    fn emit_pass(
+        &mut self,
+        pass: &Pass,
+        part: BandPart,
+        output_band: usize,
+        cb: &mut impl FnMut(SixelEvent),
+    ) {
+        let mask = self.row_mask(output_band);
+        let runs: Vec<Run> = pass
+            .runs
+            .iter()
+            .map(|run| Run {
+                byte: (self.shift(run.byte.wrapping_sub(BLANK), part) & mask) + BLANK,
+                ..*run
+            })
+            .collect();
+        let painted_runs = match runs.iter().rposition(|run| run.byte != BLANK) {
+            Some(last_painted_run) => &runs[..=last_painted_run],
+            None => return,
+        };
+        // bands that end up empty are only emitted if a later band has data
+        while self.output_band < output_band {
+            cb(SixelEvent::GotoNextLine);
+            self.output_band += 1;
+            self.output_band_has_pass = false;
+        }
+        if self.output_band_has_pass {
+            cb(SixelEvent::GotoBeginningOfLine);
+        }
+        self.output_band_has_pass = true;
+        // passes before the first color introducer paint with register 0
+        let color = pass.color.unwrap_or(0);
+        if (pass.color.is_some() || self.emitted_color.is_some())
+            && self.emitted_color != Some(color)
+        {
+            cb(SixelEvent::ColorIntroducer {
+                color_number: color,
+                color_coordinate_system: None,
+            });
+            self.emitted_color = Some(color);
+        }
+        if pass.start > 0 {
+            emit_run(
+                Run {
+                    byte: BLANK,
+                    count: pass.start,
+                    is_repeat: pass.start > 3,
+                },
+                cb,
+            );
+        }
+        for run in painted_runs {
+            emit_run(*run, cb);
+        }
+    }
+}
+
+// the color of a register, registers that were not defined have their default color
+fn register_color(
+    registers: &BTreeMap<u16, ColorCoordinateSystem>,
+    register: u16,
+) -> ColorCoordinateSystem {
+    registers
+        .get(&register)
+        .copied()
+        .unwrap_or_else(|| vt340_color(register))
+}
+
+fn emit_run(run: Run, cb: &mut impl FnMut(SixelEvent)) {
+    if run.is_repeat && run.count > 1 {
+        cb(SixelEvent::Repeat {
+            repeat_count: run.count,
+            byte_to_repeat: run.byte,
+        });
+    } else {
+        for _ in 0..run.count {
+            cb(SixelEvent::Data { byte: run.byte });
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./crop_tests.rs"]
+mod tests;
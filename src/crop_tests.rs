@@ -0,0 +1,154 @@
+use std::str;
+
+use crate::decoder::decode_with;
+use crate::{Bitmap, Crop, Decoder, Parser, SixelEvent};
+
+fn crop(sample: &str, x: usize, y: usize, width: usize, height: usize) -> String {
+    let mut parser = Parser::new();
+    let mut crop = Crop::new(x, y, width, height);
+    let mut serialized = vec![];
+    for byte in sample.as_bytes() {
+        parser.advance(byte, |sixel_event| {
+            crop.advance(sixel_event, |event| {
+                event.write_to(&mut serialized).unwrap()
+            })
+        });
+    }
+    crop.finish(|event| event.write_to(&mut serialized).unwrap());
+    String::from_utf8(serialized).unwrap()
+}
+
+#[test]
+fn horizontal_crop() {
+    let sample = "\u{1b}Pq\"1;1;8;6#1;2;100;0;0#1!8~\u{1b}\\";
+    assert_eq!(
+        crop(sample, 2, 0, 4, 6),
+        "\u{1b}Pq\"1;1;4;6#1;2;100;0;0!4~\u{1b}\\"
+    );
+}
+
+#[test]
+fn crop_to_whole_bands() {
+    let sample = "\u{1b}Pq#1~~-#2@@-#1NN\u{1b}\\";
+    assert_eq!(crop(sample, 0, 6, 2, 12), "\u{1b}Pq#2@@-#1NN\u{1b}\\");
+    assert_eq!(crop(sample, 0, 0, 2, 6), "\u{1b}Pq#1~~\u{1b}\\");
+}
+
+#[test]
+fn crop_that_is_not_aligned_to_bands() {
+    // rows 3, 4 and 5 of the first band end up on top of row 0 of the second band
+    let sample = "\u{1b}Pq#1~~-@@\u{1b}\\";
+    assert_eq!(crop(sample, 0, 3, 2, 6), "\u{1b}Pq#1FF$GG\u{1b}\\");
+}
+
+#[test]
+fn crop_that_is_not_aligned_to_bands_spans_several_output_bands() {
+    let sample = "\u{1b}Pq#1~-#2~-#3~\u{1b}\\";
+    assert_eq!(crop(sample, 0, 2, 1, 14), "\u{1b}Pq#1N$#2o-N$#3o-B\u{1b}\\");
+}
+
+#[test]
+fn rows_below_the_crop_are_masked() {
+    let sample = "\u{1b}Pq#1~~-~~\u{1b}\\";
+    assert_eq!(crop(sample, 0, 0, 2, 4), "\u{1b}Pq#1NN\u{1b}\\");
+}
+
+#[test]
+fn repeats_are_clipped() {
+    let sample = "\u{1b}Pq#1!10~@@@@\u{1b}\\";
+    assert_eq!(crop(sample, 2, 0, 10, 6), "\u{1b}Pq#1!8~@@\u{1b}\\");
+}
+
+#[test]
+fn passes_that_become_empty_are_dropped() {
+    let sample = "\u{1b}Pq#1@@$#2__$#3@@\u{1b}\\";
+    assert_eq!(crop(sample, 0, 0, 2, 3), "\u{1b}Pq#1@@$#3@@\u{1b}\\");
+}
+
+#[test]
+fn color_change_in_the_middle_of_a_pass() {
+    let sample = "\u{1b}Pq#1~~#2~~\u{1b}\\";
+    assert_eq!(crop(sample, 1, 0, 3, 6), "\u{1b}Pq#1~$#2?~~\u{1b}\\");
+}
+
+#[test]
+fn raster_attributes_are_rewritten() {
+    let sample = "\u{1b}Pq\"2;1;10;10\u{1b}\\";
+    assert_eq!(crop(sample, 2, 3, 20, 4), "\u{1b}Pq\"2;1;8;4\u{1b}\\");
+    assert_eq!(crop(sample, 20, 30, 5, 5), "\u{1b}Pq\"2;1;0;0\u{1b}\\");
+}
+
+#[test]
+fn color_definitions_outside_of_the_crop_are_kept() {
+    let sample = "\u{1b}Pq#1;2;100;0;0#1~~-#2;2;0;100;0#2~~-#1~~\u{1b}\\";
+    assert_eq!(
+        crop(sample, 0, 12, 2, 6),
+        "\u{1b}Pq#1;2;100;0;0#2;2;0;100;0#1~~\u{1b}\\"
+    );
+}
+
+#[test]
+fn events_outside_of_images_are_passed_through() {
+    let mut crop = Crop::new(0, 0, 1, 1);
+    let mut events = vec![];
+    crop.advance(SixelEvent::GotoNextLine, |event| events.push(event));
+    crop.advance(SixelEvent::End, |event| events.push(event));
+    assert_eq!(events, vec![SixelEvent::GotoNextLine, SixelEvent::End]);
+}
+
+// compares the decoded crop with the same area of the decoded image, pixel by pixel
+fn assert_crop_matches_decoded_image(
+    sample: &[u8],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) {
+    let background = [1, 2, 3, 4];
+    let decode = |bytes: &[u8]| {
+        let mut decoder = Decoder::new();
+        decoder.set_background(background);
+        let mut images = vec![];
+        decode_with(decoder, bytes, |image| images.push(image));
+        images.remove(0)
+    };
+    let cropped = crop(str::from_utf8(sample).unwrap(), x, y, width, height);
+    let image = decode(sample);
+    let cropped_image = decode(cropped.as_bytes());
+    let pixel = |image: &Bitmap, x: usize, y: usize| {
+        if x < image.width && y < image.height {
+            image.pixel(x, y)
+        } else {
+            background
+        }
+    };
+    for row in 0..height {
+        for column in 0..width {
+            assert_eq!(
+                pixel(&cropped_image, column, row),
+                pixel(&image, x + column, y + row),
+                "pixel {},{} of {:?}",
+                column,
+                row,
+                cropped
+            );
+        }
+    }
+}
+
+#[test]
+fn crop_that_is_not_aligned_to_bands_keeps_colors_defined_mid_band() {
+    // register 3 is used with its default color and redefined later in the band
+    let sample = b"\x1bPqmMXv#3#3#3SGDn#0#0$#3;2;54;1;51[!2~f-\x1b\\";
+    assert_crop_matches_decoded_image(sample, 3, 5, 7, 4);
+    let sample = b"\x1bPq#1;2;100;0;0#1~~$#1;2;0;0;100??~~-#1~~~~\x1b\\";
+    for y in 0..12 {
+        assert_crop_matches_decoded_image(sample, 0, y, 4, 6);
+    }
+}
+
+#[test]
+fn crop_selects_register_0_for_passes_without_a_color() {
+    let sample = b"\x1bPq~~$#1;2;100;0;0??~~-~~~~\x1b\\";
+    assert_crop_matches_decoded_image(sample, 0, 2, 4, 7);
+}
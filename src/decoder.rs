@@ -0,0 +1,265 @@
+//! Decodes [`SixelEvent`] streams into RGBA [`Bitmap`]s.
+//!
+//! Each image starts with the VT340's default palette (see [`Palette::vt340`]), or with the
+//! registers that the previous images left behind if they are shared (see [`RegisterMode`]).
+//! Pixels are painted with the color their register holds at the time, so redefining a register
+//! does not change pixels that were already painted with it.
+//!
+//! The size of a decoded image is the larger of its declared size (in its raster attributes) and
+//! the area its sixel data paints, clipped to the decoder's [`Limits`]. Sixel data outside of the
+//! limits is ignored. Unpainted pixels are transparent if the image asked for a transparent
+//! background (`P2` = 1) and the decoder's background color otherwise.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::decoder::decode;
+//!
+//! let images = decode(b"\x1bPq\"1;1;2;6#1;2;100;0;0~~\x1b\\");
+//! assert_eq!((images[0].width, images[0].height), (2, 6));
+//! assert_eq!(images[0].pixel(1, 5), [255, 0, 0, 255]);
+//! ```
+
+use std::ops::Range;
+
+use crate::placement::ImageExtent;
+use crate::{Bitmap, Palette, Parser, RegisterMode, SixelEvent, SliceEvent};
+
+/// Bounds on the resources a [`Decoder`] uses for an image. Terminals report (and let
+/// applications change) these with XTSMGRAPHICS, see [`query`](crate::query).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub max_width: usize,
+    pub max_height: usize,
+    /// The number of color registers each image has.
+    pub color_registers: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_width: 4096,
+            max_height: 4096,
+            color_registers: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    limits: Limits,
+    background: [u8; 4],
+    register_mode: RegisterMode,
+    // the registers shared between images in `RegisterMode::Shared`
+    shared_palette: Palette,
+    image: Option<ImageState>,
+}
+
+#[derive(Clone, Debug)]
+struct ImageState {
+    // the decoder's limits when the image started
+    limits: Limits,
+    palette: Palette,
+    color: [u8; 4],
+    transparent_background: bool,
+    pixel_aspect_ratio: (usize, usize),
+    // the size of the image before it is clipped to the limits
+    extent: ImageExtent,
+    // painted pixels, unpainted ones have an alpha of 0
+    rows: Vec<Vec<[u8; 4]>>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::with_limits(Limits::default())
+    }
+    pub fn with_limits(limits: Limits) -> Self {
+        Decoder {
+            limits,
+            background: [0, 0, 0, 255],
+            register_mode: RegisterMode::Private,
+            shared_palette: Palette::vt340(limits.color_registers),
+            image: None,
+        }
+    }
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+    /// Changes the limits, for instance after an XTSMGRAPHICS set or reset (see
+    /// [`Limits::answer`]). Takes effect from the next image on. If the number of color registers
+    /// changes, the shared color registers are reset to the default palette.
+    pub fn set_limits(&mut self, limits: Limits) {
+        if limits.color_registers != self.limits.color_registers {
+            self.shared_palette = Palette::vt340(limits.color_registers);
+        }
+        self.limits = limits;
+    }
+    /// Sets the color of unpainted pixels in images without a transparent background. Defaults to
+    /// opaque black.
+    pub fn set_background(&mut self, background: [u8; 4]) {
+        self.background = background;
+    }
+    pub fn register_mode(&self) -> RegisterMode {
+        self.register_mode
+    }
+    /// Sets whether images get private color registers or share them. Takes effect from the next
+    /// image on.
+    pub fn set_register_mode(&mut self, register_mode: RegisterMode) {
+        self.register_mode = register_mode;
+    }
+    /// Resets the shared color registers to the default palette.
+    pub fn reset_palette(&mut self) {
+        self.shared_palette = Palette::vt340(self.limits.color_registers);
+    }
+    /// Feeds a single event to the decoder. Once an image ends, it is passed to the callback.
+    /// Events outside of images are ignored.
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(Bitmap)) {
+        if let SixelEvent::Dcs {
+            macro_parameter,
+            transparent_background,
+            ..
+        } = event
+        {
+            self.finish(&mut cb);
+            let palette = match self.register_mode {
+                RegisterMode::Private => Palette::vt340(self.limits.color_registers),
+                RegisterMode::Shared => self.shared_palette.clone(),
+            };
+            self.image = Some(ImageState {
+                limits: self.limits,
+                palette,
+                color: [0, 0, 0, 0],
+                transparent_background: transparent_background == Some(1),
+                pixel_aspect_ratio: macro_parameter_aspect_ratio(macro_parameter),
+                extent: ImageExtent::default(),
+                rows: vec![],
+            });
+            let image = self.image.as_mut().unwrap();
+            image.color = image.palette.get(0);
+            return;
+        }
+        let image = match self.image.as_mut() {
+            Some(image) => image,
+            None => return,
+        };
+        let (column, band) = (image.extent.column(), image.extent.band());
+        image.extent.advance(event);
+        match event {
+            SixelEvent::RasterAttribute { pan, pad, .. } => image.pixel_aspect_ratio = (pan, pad),
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system,
+            } => {
+                if let Some(color_coordinate_system) = color_coordinate_system {
+                    image.palette.set(color_number, color_coordinate_system);
+                }
+                image.color = image.palette.get(color_number);
+            }
+            SixelEvent::Data { byte } => image.paint(byte, column..image.extent.column(), band),
+            SixelEvent::Repeat { byte_to_repeat, .. } => {
+                image.paint(byte_to_repeat, column..image.extent.column(), band)
+            }
+            SixelEvent::End | SixelEvent::Unterminated => self.finish(cb),
+            SixelEvent::Dcs { .. }
+            | SixelEvent::GotoBeginningOfLine
+            | SixelEvent::GotoNextLine
+            | SixelEvent::UnknownSequence(_) => {}
+        }
+    }
+    /// Passes an image that was not ended by an `End` or `Unterminated` event to the callback.
+    pub fn finish(&mut self, mut cb: impl FnMut(Bitmap)) {
+        if let Some(mut image) = self.image.take() {
+            if self.register_mode == RegisterMode::Shared {
+                std::mem::swap(&mut self.shared_palette, &mut image.palette);
+            }
+            cb(image.into_bitmap(self.background));
+        }
+    }
+}
+
+impl ImageState {
+    // paints the columns of a band, as far as they are within the limits
+    fn paint(&mut self, byte: u8, columns: Range<usize>, band: usize) {
+        let limits = &self.limits;
+        let bits = byte.wrapping_sub(b'?') & 0b111111;
+        let (start, end) = (columns.start, std::cmp::min(columns.end, limits.max_width));
+        if bits == 0 || start >= end {
+            return;
+        }
+        for row in 0..6 {
+            let y = band.saturating_mul(6).saturating_add(row);
+            if bits & (1 << row) == 0 || y >= limits.max_height {
+                continue;
+            }
+            if self.rows.len() <= y {
+                self.rows.resize(y + 1, vec![]);
+            }
+            let pixels = &mut self.rows[y];
+            if pixels.len() < end {
+                pixels.resize(end, [0, 0, 0, 0]);
+            }
+            pixels[start..end].fill(self.color);
+        }
+    }
+    fn into_bitmap(self, background: [u8; 4]) -> Bitmap {
+        let width = std::cmp::min(self.extent.width, self.limits.max_width);
+        let height = std::cmp::min(self.extent.height, self.limits.max_height);
+        let background = if self.transparent_background {
+            [0, 0, 0, 0]
+        } else {
+            background
+        };
+        let mut bitmap = Bitmap::from_rgba(width, height, background.repeat(width * height));
+        bitmap.pixel_aspect_ratio = self.pixel_aspect_ratio;
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if pixel[3] != 0 {
+                    bitmap.set_pixel(x, y, *pixel);
+                }
+            }
+        }
+        bitmap
+    }
+}
+
+// the pixel aspect ratio selected by P1, for images without raster attributes
+fn macro_parameter_aspect_ratio(macro_parameter: Option<u8>) -> (usize, usize) {
+    match macro_parameter.unwrap_or(0) {
+        2 => (5, 1),
+        3 | 4 => (3, 1),
+        7..=9 => (1, 1),
+        _ => (2, 1),
+    }
+}
+
+/// Decodes every image in a buffer of sixel bytes with the default [`Limits`].
+pub fn decode(bytes: &[u8]) -> Vec<Bitmap> {
+    let mut images = vec![];
+    decode_with(Decoder::new(), bytes, |image| images.push(image));
+    images
+}
+
+/// Decodes every image in a buffer of sixel bytes with the given decoder, which is finished at the
+/// end of the buffer.
+pub fn decode_with(mut decoder: Decoder, bytes: &[u8], mut cb: impl FnMut(Bitmap)) {
+    let mut parser = Parser::new();
+    parser.advance_slice(bytes, |event| match event {
+        SliceEvent::DataRun(run) => {
+            for byte in run {
+                decoder.advance(SixelEvent::Data { byte: *byte }, &mut cb);
+            }
+        }
+        SliceEvent::Event(event) => decoder.advance(event, &mut cb),
+    });
+    parser.finish(|event| decoder.advance(event, &mut cb));
+    decoder.finish(&mut cb);
+}
+
+#[cfg(test)]
+#[path = "./decoder_tests.rs"]
+mod tests;
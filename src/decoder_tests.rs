@@ -0,0 +1,194 @@
+use super::*;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+fn decode_one(bytes: &[u8]) -> Bitmap {
+    let mut images = decode(bytes);
+    assert_eq!(images.len(), 1);
+    images.remove(0)
+}
+
+// the top-to-bottom colors of a column
+fn column(bitmap: &Bitmap, x: usize) -> Vec<[u8; 4]> {
+    (0..bitmap.height).map(|y| bitmap.pixel(x, y)).collect()
+}
+
+#[test]
+fn decode_bands_and_passes() {
+    let bitmap = decode_one(b"\x1bPq#1;2;100;0;0#2;2;0;0;100#1N$#2o-#1@\x1b\\");
+    assert_eq!((bitmap.width, bitmap.height), (1, 7));
+    assert_eq!(
+        column(&bitmap, 0),
+        vec![RED, RED, RED, RED, BLUE, BLUE, RED]
+    );
+}
+
+#[test]
+fn repeat_and_beginning_of_line() {
+    let bitmap = decode_one(b"\x1bPq#1;2;100;0;0!3~$#2;2;0;0;100@\x1b\\");
+    assert_eq!((bitmap.width, bitmap.height), (3, 6));
+    assert_eq!(bitmap.pixel(0, 0), BLUE);
+    assert_eq!(bitmap.pixel(1, 0), RED);
+    assert_eq!(bitmap.pixel(2, 5), RED);
+}
+
+#[test]
+fn declared_size_and_background() {
+    let bitmap = decode_one(b"\x1bPq\"1;1;3;8#1;2;100;0;0@\x1b\\");
+    assert_eq!((bitmap.width, bitmap.height), (3, 8));
+    assert_eq!(bitmap.pixel_aspect_ratio, (1, 1));
+    assert_eq!(bitmap.pixel(0, 0), RED);
+    assert_eq!(bitmap.pixel(0, 1), BLACK);
+    assert_eq!(bitmap.pixel(2, 7), BLACK);
+}
+
+#[test]
+fn custom_background() {
+    let mut decoder = Decoder::new();
+    decoder.set_background([1, 2, 3, 255]);
+    let mut images = vec![];
+    decode_with(decoder, b"\x1bPq\"1;1;1;2#1;2;100;0;0@\x1b\\", |image| {
+        images.push(image)
+    });
+    assert_eq!(images[0].pixel(0, 1), [1, 2, 3, 255]);
+}
+
+#[test]
+fn transparent_background() {
+    let bitmap = decode_one(b"\x1bP0;1q\"1;1;2;6#1;2;100;0;0@\x1b\\");
+    assert_eq!(bitmap.pixel(0, 0), RED);
+    assert_eq!(bitmap.pixel(0, 1), TRANSPARENT);
+    assert_eq!(bitmap.pixel(1, 0), TRANSPARENT);
+}
+
+#[test]
+fn default_palette() {
+    let bitmap = decode_one(b"\x1bPq#2~#15~\x1b\\");
+    assert_eq!(bitmap.pixel(0, 0), [204, 33, 33, 255]);
+    assert_eq!(bitmap.pixel(1, 0), [204, 204, 204, 255]);
+}
+
+#[test]
+fn redefined_register_does_not_repaint() {
+    let bitmap = decode_one(b"\x1bPq#1;2;100;0;0~#1;2;0;0;100~\x1b\\");
+    assert_eq!(bitmap.pixel(0, 0), RED);
+    assert_eq!(bitmap.pixel(1, 0), BLUE);
+}
+
+#[test]
+fn palette_does_not_carry_over_to_the_next_image() {
+    let images = decode(b"\x1bPq#1;2;100;0;0~\x1b\\\x1bPq#1~\x1b\\");
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[0].pixel(0, 0), RED);
+    assert_eq!(images[1].pixel(0, 0), [51, 51, 204, 255]);
+}
+
+#[test]
+fn aspect_ratio_from_macro_parameter() {
+    let aspect_ratio = |bytes: &[u8]| decode_one(bytes).pixel_aspect_ratio;
+    assert_eq!(aspect_ratio(b"\x1bPq~\x1b\\"), (2, 1));
+    assert_eq!(aspect_ratio(b"\x1bP2q~\x1b\\"), (5, 1));
+    assert_eq!(aspect_ratio(b"\x1bP3q~\x1b\\"), (3, 1));
+    assert_eq!(aspect_ratio(b"\x1bP9q~\x1b\\"), (1, 1));
+    assert_eq!(aspect_ratio(b"\x1bP2q\"3;4~\x1b\\"), (3, 4));
+}
+
+#[test]
+fn limits_clip_the_image() {
+    let limits = Limits {
+        max_width: 4,
+        max_height: 8,
+        color_registers: 16,
+    };
+    let mut images = vec![];
+    let bytes = b"\x1bPq\"1;1;100;100#1;2;100;0;0!1000~-~-~\x1b\\";
+    decode_with(Decoder::with_limits(limits), bytes, |image| {
+        images.push(image)
+    });
+    assert_eq!((images[0].width, images[0].height), (4, 8));
+    assert_eq!(images[0].pixel(3, 5), RED);
+    assert_eq!(images[0].pixel(0, 7), RED);
+}
+
+#[test]
+fn set_limits_applies_to_the_next_image() {
+    let mut decoder = Decoder::new();
+    let mut images = vec![];
+    let mut parser = Parser::new();
+    for byte in b"\x1bPq!5~" {
+        parser.advance(byte, |event| {
+            decoder.advance(event, |image| images.push(image))
+        });
+    }
+    decoder.set_limits(Limits {
+        max_width: 4,
+        ..Limits::default()
+    });
+    assert_eq!(decoder.limits().max_width, 4);
+    for byte in b"!5~\x1b\\\x1bPq!10~\x1b\\" {
+        parser.advance(byte, |event| {
+            decoder.advance(event, |image| images.push(image))
+        });
+    }
+    assert_eq!(images[0].width, 10);
+    assert_eq!(images[1].width, 4);
+}
+
+#[test]
+fn huge_repeat_count() {
+    let bitmap = decode_one(b"\x1bPq!9999~!9999~\x1b\\");
+    assert_eq!(bitmap.width, Limits::default().max_width);
+}
+
+#[test]
+fn zero_repeat_count_paints_nothing() {
+    let bitmap = decode_one(b"\x1bPq\"1;1;2;6#1;2;100;0;0#1~~-#1!0~\x1b\\");
+    assert_eq!((bitmap.width, bitmap.height), (2, 6));
+}
+
+#[test]
+fn unterminated_image() {
+    let bitmap = decode_one(b"\x1bPq#1;2;100;0;0~");
+    assert_eq!(bitmap.pixel(0, 0), RED);
+}
+
+#[test]
+fn events_outside_of_images_are_ignored() {
+    let images = decode(b"~~-\x1bPq@\x1b\\~~");
+    assert_eq!(images.len(), 1);
+    assert_eq!((images[0].width, images[0].height), (1, 1));
+}
+
+#[test]
+fn shared_registers_carry_over_to_the_next_image() {
+    let mut decoder = Decoder::new();
+    decoder.set_register_mode(RegisterMode::Shared);
+    let mut images = vec![];
+    let bytes = b"\x1bPq#1;2;100;0;0~\x1b\\\x1bPq#1~#2;2;0;0;100\x1b\\\x1bPq#1~#2~\x1b\\";
+    decode_with(decoder, bytes, |image| images.push(image));
+    assert_eq!(images[1].pixel(0, 0), RED);
+    assert_eq!(images[2].pixel(0, 0), RED);
+    assert_eq!(images[2].pixel(1, 0), BLUE);
+}
+
+#[test]
+fn reset_shared_registers() {
+    let mut decoder = Decoder::new();
+    decoder.set_register_mode(RegisterMode::Shared);
+    let mut images = vec![];
+    let mut parser = Parser::new();
+    let mut decode = |decoder: &mut Decoder, bytes: &[u8]| {
+        for byte in bytes {
+            parser.advance(byte, |event| {
+                decoder.advance(event, |image| images.push(image))
+            });
+        }
+    };
+    decode(&mut decoder, b"\x1bPq#1;2;100;0;0~\x1b\\");
+    decoder.reset_palette();
+    decode(&mut decoder, b"\x1bPq#1~\x1b\\");
+    assert_eq!(images[1].pixel(0, 0), [51, 51, 204, 255]);
+}
@@ -0,0 +1,205 @@
+//! Extracts sixel images from arbitrary terminal output.
+//!
+//! [`Demultiplexer`] scans a byte stream (eg. the output of a PTY) for sixel device control strings
+//! (`ESC P <params> q ... ESC \`) and tokenizes their content with a [`Parser`]. All other bytes,
+//! including text, CSI sequences and other device control strings (eg. DECRQSS or tmux
+//! passthrough), are handed back untouched.
+//!
+//! Only 7-bit introducers are recognized, since 8-bit C1 controls collide with UTF-8.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::demultiplexer::{Demultiplexer, Handler};
+//! use sixel_tokenizer::SixelEvent;
+//!
+//! #[derive(Default)]
+//! struct Terminal {
+//!     text: Vec<u8>,
+//!     sixel_events: Vec<SixelEvent>,
+//! }
+//!
+//! impl Handler for Terminal {
+//!     fn on_sixel_event(&mut self, event: SixelEvent) {
+//!         self.sixel_events.push(event);
+//!     }
+//!     fn on_passthrough(&mut self, bytes: &[u8]) {
+//!         self.text.extend_from_slice(bytes);
+//!     }
+//! }
+//!
+//! let mut terminal = Terminal::default();
+//! let mut demultiplexer = Demultiplexer::new();
+//! demultiplexer.advance(b"foo\x1b[1mbar\x1bPq#1~~\x1b\\baz", &mut terminal);
+//! assert_eq!(terminal.text, b"foo\x1b[1mbarbaz");
+//! assert_eq!(terminal.sixel_events.len(), 5);
+//! ```
+
+use arrayvec::ArrayVec;
+
+use crate::{Parser, SixelEvent};
+
+const ESC: u8 = 27;
+
+pub trait Handler {
+    fn on_sixel_event(&mut self, event: SixelEvent);
+    /// Called with bytes that are not part of a sixel image. Bytes are passed through in order, but
+    /// may be split differently than they were given to [`Demultiplexer::advance`].
+    fn on_passthrough(&mut self, bytes: &[u8]);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    DcsHeader,
+    Sixel,
+    SixelEscape,
+    OtherDcs,
+    OtherDcsEscape,
+}
+
+#[derive(Clone, Debug)]
+pub struct Demultiplexer {
+    state: State,
+    parser: Parser,
+    // bytes that might be the start of a sixel image, held back until we know
+    header: ArrayVec<u8, 64>,
+}
+
+impl Default for Demultiplexer {
+    fn default() -> Self {
+        Demultiplexer::new()
+    }
+}
+
+impl Demultiplexer {
+    pub fn new() -> Self {
+        Demultiplexer {
+            state: State::Ground,
+            parser: Parser::new(),
+            header: ArrayVec::new(),
+        }
+    }
+    pub fn advance(&mut self, bytes: &[u8], handler: &mut impl Handler) {
+        let mut passthrough_start = None;
+        for i in 0..bytes.len() {
+            // a byte that ends a sequence we were holding back is processed again in the new state
+            while !self.process_byte(bytes, i, &mut passthrough_start, handler) {}
+        }
+        flush_passthrough(bytes, bytes.len(), &mut passthrough_start, handler);
+    }
+    fn process_byte(
+        &mut self,
+        bytes: &[u8],
+        i: usize,
+        passthrough_start: &mut Option<usize>,
+        handler: &mut impl Handler,
+    ) -> bool {
+        let byte = bytes[i];
+        match (self.state, byte) {
+            (State::Ground, ESC) => {
+                flush_passthrough(bytes, i, passthrough_start, handler);
+                self.hold_back_escape();
+            }
+            (State::Ground, _) => {
+                passthrough_start.get_or_insert(i);
+            }
+            (State::Escape, b'P') => {
+                self.header.push(byte);
+                self.state = State::DcsHeader;
+            }
+            (State::Escape, _) => {
+                self.release_header(State::Ground, handler);
+                return false;
+            }
+            (State::DcsHeader, b'0'..=b'9' | b';') => {
+                if self.header.try_push(byte).is_err() {
+                    self.release_header(State::OtherDcs, handler);
+                    return false;
+                }
+            }
+            (State::DcsHeader, b'q') => {
+                let parser = &mut self.parser;
+                for header_byte in self.header.drain(..).chain(Some(byte)) {
+                    parser.advance(&header_byte, |event| handler.on_sixel_event(event));
+                }
+                self.state = State::Sixel;
+            }
+            (State::DcsHeader, ESC) => {
+                self.release_header(State::Ground, handler);
+                return false;
+            }
+            (State::DcsHeader, _) => {
+                self.release_header(State::OtherDcs, handler);
+                return false;
+            }
+            (State::Sixel, ESC) => self.state = State::SixelEscape,
+            (State::Sixel, _) => {
+                self.parser
+                    .advance(&byte, |event| handler.on_sixel_event(event));
+            }
+            (State::SixelEscape, b'\\') => {
+                for terminator_byte in [ESC, byte] {
+                    self.parser
+                        .advance(&terminator_byte, |event| handler.on_sixel_event(event));
+                }
+                self.state = State::Ground;
+            }
+            (State::SixelEscape, _) => {
+                // an escape sequence that is not ST aborts the image
+                self.parser.finish(|event| handler.on_sixel_event(event));
+                self.hold_back_escape();
+                return false;
+            }
+            (State::OtherDcs, ESC) => {
+                flush_passthrough(bytes, i, passthrough_start, handler);
+                self.hold_back_escape();
+                self.state = State::OtherDcsEscape;
+            }
+            (State::OtherDcs, _) => {
+                passthrough_start.get_or_insert(i);
+            }
+            (State::OtherDcsEscape, b'\\') => {
+                self.release_header(State::Ground, handler);
+                passthrough_start.get_or_insert(i);
+            }
+            (State::OtherDcsEscape, ESC) => {
+                // tmux passthrough doubles the escape characters it wraps
+                self.release_header(State::OtherDcs, handler);
+                passthrough_start.get_or_insert(i);
+            }
+            (State::OtherDcsEscape, _) => {
+                self.state = State::Escape;
+                return false;
+            }
+        }
+        true
+    }
+    fn hold_back_escape(&mut self) {
+        self.header.clear();
+        self.header.push(ESC);
+        self.state = State::Escape;
+    }
+    fn release_header(&mut self, next_state: State, handler: &mut impl Handler) {
+        handler.on_passthrough(&self.header);
+        self.header.clear();
+        self.state = next_state;
+    }
+}
+
+fn flush_passthrough(
+    bytes: &[u8],
+    end: usize,
+    passthrough_start: &mut Option<usize>,
+    handler: &mut impl Handler,
+) {
+    if let Some(start) = passthrough_start.take() {
+        if start < end {
+            handler.on_passthrough(&bytes[start..end]);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./demultiplexer_tests.rs"]
+mod tests;
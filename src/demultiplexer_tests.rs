@@ -0,0 +1,110 @@
+use crate::demultiplexer::{Demultiplexer, Handler};
+use crate::SixelEvent;
+
+#[derive(Default)]
+struct Recorder {
+    sixel_events: Vec<SixelEvent>,
+    passthrough: Vec<u8>,
+}
+
+impl Handler for Recorder {
+    fn on_sixel_event(&mut self, event: SixelEvent) {
+        self.sixel_events.push(event);
+    }
+    fn on_passthrough(&mut self, bytes: &[u8]) {
+        assert!(!bytes.is_empty());
+        self.passthrough.extend_from_slice(bytes);
+    }
+}
+
+fn demultiplex(sample: &[u8]) -> Recorder {
+    let mut recorder = Recorder::default();
+    let mut demultiplexer = Demultiplexer::new();
+    demultiplexer.advance(sample, &mut recorder);
+
+    // the result must not depend on how the input is split
+    let mut byte_by_byte = Recorder::default();
+    let mut demultiplexer = Demultiplexer::new();
+    for byte in sample {
+        demultiplexer.advance(&[*byte], &mut byte_by_byte);
+    }
+    assert_eq!(recorder.sixel_events, byte_by_byte.sixel_events);
+    assert_eq!(recorder.passthrough, byte_by_byte.passthrough);
+    recorder
+}
+
+#[test]
+fn sixel_between_text_and_csi_sequences() {
+    let recorder = demultiplex(b"hello \x1b[31mred\x1bP0;1;0q#1~~\x1b\\ world\x1b[0m");
+    assert_eq!(recorder.passthrough, b"hello \x1b[31mred world\x1b[0m");
+    let expected = vec![
+        SixelEvent::new_dcs(Some(0), Some(1), Some(0)),
+        SixelEvent::new_color_introducer(1, None, None, None, None).unwrap(),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::End,
+    ];
+    assert_eq!(recorder.sixel_events, expected);
+}
+
+#[test]
+fn text_only() {
+    let recorder = demultiplex("plain text, ünicode \x1b]0;title\x07\x1bM".as_bytes());
+    assert_eq!(
+        recorder.passthrough,
+        "plain text, ünicode \x1b]0;title\x07\x1bM".as_bytes()
+    );
+    assert_eq!(recorder.sixel_events, vec![]);
+}
+
+#[test]
+fn decrqss_is_passed_through() {
+    let sample = b"\x1bP$qm\x1b\\\x1bP1$r0m\x1b\\";
+    let recorder = demultiplex(sample);
+    assert_eq!(recorder.passthrough, sample);
+    assert_eq!(recorder.sixel_events, vec![]);
+}
+
+#[test]
+fn tmux_passthrough_is_passed_through() {
+    let sample = b"\x1bPtmux;\x1b\x1bPq#1~\x1b\x1b\\\x1b\\after";
+    let recorder = demultiplex(sample);
+    assert_eq!(recorder.passthrough, sample);
+    assert_eq!(recorder.sixel_events, vec![]);
+}
+
+#[test]
+fn escape_sequence_inside_of_sixel_aborts_the_image() {
+    let recorder = demultiplex(b"\x1bPq~~\x1b[0mtext\x1bPq@\x1b\\");
+    assert_eq!(recorder.passthrough, b"\x1b[0mtext");
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::Unterminated,
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::Data { byte: b'@' },
+        SixelEvent::End,
+    ];
+    assert_eq!(recorder.sixel_events, expected);
+}
+
+#[test]
+fn escape_at_the_end_of_input_is_held_back() {
+    let mut recorder = Recorder::default();
+    let mut demultiplexer = Demultiplexer::new();
+    demultiplexer.advance(b"text\x1b", &mut recorder);
+    assert_eq!(recorder.passthrough, b"text");
+    demultiplexer.advance(b"[m", &mut recorder);
+    assert_eq!(recorder.passthrough, b"text\x1b[m");
+}
+
+#[test]
+fn overlong_dcs_parameters_are_passed_through() {
+    let mut sample = b"\x1bP".to_vec();
+    sample.extend([b'1'; 100]);
+    sample.extend_from_slice(b"q~\x1b\\");
+    let recorder = demultiplex(&sample);
+    assert_eq!(recorder.passthrough, sample);
+    assert_eq!(recorder.sixel_events, vec![]);
+}
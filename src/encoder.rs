@@ -0,0 +1,311 @@
+//! Encodes RGBA [`Bitmap`]s into sixel images.
+//!
+//! Images with at most [`Encoder::max_colors`] distinct colors keep all of them. Other images are
+//! reduced to a palette by median cut and optionally dithered. Sixel colors are given in percent,
+//! so every palette color is rounded to the nearest color that a percentage can express.
+//!
+//! Pixels whose alpha is below [`Encoder::alpha_threshold`] are left unpainted, and the image asks
+//! for a transparent background if there are any.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::decoder::decode;
+//! use sixel_tokenizer::{Bitmap, Encoder};
+//!
+//! let red = Bitmap::from_rgba(1, 1, vec![255, 0, 0, 255]);
+//! let mut sixel = vec![];
+//! Encoder::new().encode_to(&red, &mut sixel).unwrap();
+//! assert_eq!(sixel, b"\x1bPq\"1;1;1;1#0;2;100;0;0#0@\x1b\\");
+//! assert_eq!(decode(&sixel), vec![red]);
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::{Bitmap, ColorCoordinateSystem, SixelEvent};
+
+// repeating fewer bytes than this is no shorter than writing them out
+const MIN_REPEAT: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dithering {
+    /// Every pixel gets the palette color nearest to it.
+    None,
+    /// Floyd–Steinberg error diffusion. Only used when the image has more colors than the palette.
+    FloydSteinberg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoder {
+    /// The largest number of color registers the image may use (at least one, at most 65536).
+    pub max_colors: usize,
+    pub dithering: Dithering,
+    /// Pixels with a lower alpha are left transparent, `None` paints every pixel.
+    pub alpha_threshold: Option<u8>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder {
+            max_colors: 256,
+            dithering: Dithering::FloydSteinberg,
+            alpha_threshold: Some(128),
+        }
+    }
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+    /// Passes the events of a sixel image of the bitmap to the callback, from its `Dcs` event to
+    /// its `End` event.
+    pub fn encode(&self, bitmap: &Bitmap, mut cb: impl FnMut(SixelEvent)) {
+        let is_opaque = |pixel: &[u8]| self.alpha_threshold.is_none_or(|t| pixel[3] >= t);
+        let pixels = bitmap.pixels.chunks_exact(4);
+        let transparent_background = pixels.clone().any(|pixel| !is_opaque(pixel));
+        let mut histogram: HashMap<[u8; 3], usize> = HashMap::new();
+        for pixel in pixels.filter(|pixel| is_opaque(pixel)) {
+            *histogram.entry([pixel[0], pixel[1], pixel[2]]).or_default() += 1;
+        }
+        let max_colors = self.max_colors.clamp(1, u16::MAX as usize + 1);
+        let exact = histogram.len() <= max_colors;
+        let palette: Vec<[u8; 3]> = if exact {
+            let mut colors: Vec<[u8; 3]> = histogram.keys().copied().collect();
+            colors.sort_unstable();
+            colors
+        } else {
+            median_cut(histogram.into_iter().collect(), max_colors)
+        };
+        let palette: Vec<[usize; 3]> = palette.iter().map(|c| c.map(to_percent)).collect();
+        let dither = !exact && self.dithering == Dithering::FloydSteinberg;
+        let indices = map_pixels(bitmap, &palette, dither, is_opaque);
+
+        let (pan, pad) = match bitmap.pixel_aspect_ratio {
+            (0, _) | (_, 0) => (1, 1),
+            aspect_ratio => aspect_ratio,
+        };
+        cb(SixelEvent::Dcs {
+            macro_parameter: if transparent_background {
+                Some(0)
+            } else {
+                None
+            },
+            transparent_background: if transparent_background {
+                Some(1)
+            } else {
+                None
+            },
+            horizontal_pixel_distance: None,
+        });
+        cb(SixelEvent::RasterAttribute {
+            pan,
+            pad,
+            ph: Some(bitmap.width),
+            pv: Some(bitmap.height),
+        });
+        for (color_number, [r, g, b]) in palette.iter().enumerate() {
+            cb(SixelEvent::ColorIntroducer {
+                color_number: color_number as u16,
+                color_coordinate_system: Some(ColorCoordinateSystem::RGB(*r, *g, *b)),
+            });
+        }
+        let mut passes: Vec<Vec<u8>> = vec![vec![]; palette.len()];
+        for band in 0..bitmap.height.div_ceil(6) {
+            if band > 0 {
+                cb(SixelEvent::GotoNextLine);
+            }
+            let mut order = vec![];
+            for row in 0..std::cmp::min(6, bitmap.height - band * 6) {
+                let y = band * 6 + row;
+                for (x, index) in indices[y * bitmap.width..(y + 1) * bitmap.width]
+                    .iter()
+                    .enumerate()
+                {
+                    if let Some(index) = index {
+                        let pass = &mut passes[*index];
+                        if pass.is_empty() {
+                            pass.resize(bitmap.width, 0);
+                            order.push(*index);
+                        }
+                        pass[x] |= 1 << row;
+                    }
+                }
+            }
+            for (i, index) in order.into_iter().enumerate() {
+                if i > 0 {
+                    cb(SixelEvent::GotoBeginningOfLine);
+                }
+                cb(SixelEvent::ColorIntroducer {
+                    color_number: index as u16,
+                    color_coordinate_system: None,
+                });
+                let pass = &mut passes[index];
+                let len = pass
+                    .iter()
+                    .rposition(|bits| *bits != 0)
+                    .map_or(0, |i| i + 1);
+                emit_pass(&pass[..len], &mut cb);
+                pass.clear();
+            }
+        }
+        cb(SixelEvent::End);
+    }
+    /// Writes a sixel image of the bitmap.
+    pub fn encode_to(&self, bitmap: &Bitmap, writer: &mut impl Write) -> io::Result<()> {
+        let mut result = Ok(());
+        self.encode(bitmap, |event| {
+            if result.is_ok() {
+                result = event.write_to(writer);
+            }
+        });
+        result
+    }
+}
+
+fn emit_pass(pass: &[u8], cb: &mut impl FnMut(SixelEvent)) {
+    let mut i = 0;
+    while i < pass.len() {
+        let count = pass[i..]
+            .iter()
+            .take_while(|bits| **bits == pass[i])
+            .count();
+        let byte = pass[i] + b'?';
+        if count >= MIN_REPEAT {
+            cb(SixelEvent::Repeat {
+                repeat_count: count,
+                byte_to_repeat: byte,
+            });
+        } else {
+            for _ in 0..count {
+                cb(SixelEvent::Data { byte });
+            }
+        }
+        i += count;
+    }
+}
+
+fn to_percent(channel: u8) -> usize {
+    (channel as usize * 100 + 127) / 255
+}
+
+fn from_percent(percent: usize) -> i32 {
+    ((percent * 255 + 50) / 100) as i32
+}
+
+// splits the box with the widest channel range at its median until there are enough boxes, and
+// returns the average color of each box
+fn median_cut(colors: Vec<([u8; 3], usize)>, max_colors: usize) -> Vec<[u8; 3]> {
+    let channel_range = |colors: &[([u8; 3], usize)], channel: usize| {
+        let values = colors.iter().map(|(color, _)| color[channel]);
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    };
+    let widest_channel = |colors: &[([u8; 3], usize)]| {
+        (0..3)
+            .map(|channel| (channel_range(colors, channel), channel))
+            .max()
+            .unwrap()
+    };
+    let mut boxes = vec![colors];
+    while boxes.len() < max_colors {
+        let (i, (range, channel)) = boxes
+            .iter()
+            .map(|colors| widest_channel(colors))
+            .enumerate()
+            .max_by_key(|(_, widest)| widest.0)
+            .unwrap();
+        if range == 0 {
+            break;
+        }
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap();
+        // both halves must hold at least one color
+        let split = (median + 1).clamp(1, colors.len() - 1);
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    boxes
+        .iter()
+        .map(|colors| {
+            let total: usize = colors.iter().map(|(_, count)| count).sum();
+            let mut sums = [0; 3];
+            for (color, count) in colors {
+                for (sum, channel) in sums.iter_mut().zip(color) {
+                    *sum += *channel as usize * count;
+                }
+            }
+            sums.map(|sum| ((sum + total / 2) / total) as u8)
+        })
+        .collect()
+}
+
+// the palette index of each pixel, `None` for transparent ones
+fn map_pixels(
+    bitmap: &Bitmap,
+    palette: &[[usize; 3]],
+    dither: bool,
+    is_opaque: impl Fn(&[u8]) -> bool,
+) -> Vec<Option<usize>> {
+    let palette: Vec<[i32; 3]> = palette.iter().map(|c| c.map(from_percent)).collect();
+    let mut nearest_cache: HashMap<[i32; 3], usize> = HashMap::new();
+    let mut nearest = |color: [i32; 3]| {
+        *nearest_cache.entry(color).or_insert_with(|| {
+            (0..palette.len())
+                .min_by_key(|i| {
+                    (0..3)
+                        .map(|c| (palette[*i][c] - color[c]).pow(2))
+                        .sum::<i32>()
+                })
+                .unwrap()
+        })
+    };
+    let width = bitmap.width;
+    let mut indices = Vec::with_capacity(width * bitmap.height);
+    // accumulated errors in 1/16ths, for this row and the next, with a column of padding each side
+    let mut errors = vec![[0i32; 3]; width + 2];
+    let mut next_errors = vec![[0i32; 3]; width + 2];
+    for y in 0..bitmap.height {
+        for x in 0..width {
+            let i = (y * width + x) * 4;
+            let pixel = &bitmap.pixels[i..i + 4];
+            if !is_opaque(pixel) {
+                indices.push(None);
+                continue;
+            }
+            let mut color = [pixel[0] as i32, pixel[1] as i32, pixel[2] as i32];
+            if dither {
+                for (channel, error) in color.iter_mut().zip(errors[x + 1]) {
+                    *channel = (*channel + error / 16).clamp(0, 255);
+                }
+            }
+            let index = nearest(color);
+            indices.push(Some(index));
+            if dither {
+                for c in 0..3 {
+                    let error = color[c] - palette[index][c];
+                    errors[x + 2][c] += error * 7;
+                    next_errors[x][c] += error * 3;
+                    next_errors[x + 1][c] += error * 5;
+                    next_errors[x + 2][c] += error;
+                }
+            }
+        }
+        std::mem::swap(&mut errors, &mut next_errors);
+        next_errors.fill([0; 3]);
+    }
+    indices
+}
+
+#[cfg(test)]
+#[path = "./encoder_tests.rs"]
+mod tests;
@@ -0,0 +1,139 @@
+use super::*;
+use crate::decoder::decode;
+
+// colors that a percentage can express exactly
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const GREY: [u8; 4] = [128, 128, 128, 255];
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+fn bitmap(width: usize, height: usize, color: impl Fn(usize, usize) -> [u8; 4]) -> Bitmap {
+    let mut bitmap = Bitmap::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            bitmap.set_pixel(x, y, color(x, y));
+        }
+    }
+    bitmap
+}
+
+fn encode_to_string(encoder: &Encoder, bitmap: &Bitmap) -> String {
+    let mut sixel = vec![];
+    encoder.encode_to(bitmap, &mut sixel).unwrap();
+    String::from_utf8(sixel).unwrap()
+}
+
+fn round_trip(encoder: &Encoder, bitmap: &Bitmap) -> Bitmap {
+    let mut images = decode(encode_to_string(encoder, bitmap).as_bytes());
+    assert_eq!(images.len(), 1);
+    images.remove(0)
+}
+
+#[test]
+fn bands_passes_and_repeats() {
+    let image = bitmap(6, 7, |x, y| if x < 2 && y < 6 { RED } else { GREEN });
+    assert_eq!(
+        encode_to_string(&Encoder::new(), &image),
+        "\u{1b}Pq\"1;1;6;7#0;2;0;100;0#1;2;100;0;0#1~~$#0??!4~-#0!6@\u{1b}\\"
+    );
+}
+
+#[test]
+fn round_trip_exact_colors() {
+    let colors = [RED, GREEN, GREY, [51, 102, 153, 255]];
+    let image = bitmap(13, 17, |x, y| colors[(x * 7 + y * y) % colors.len()]);
+    assert_eq!(round_trip(&Encoder::new(), &image), image);
+}
+
+#[test]
+fn round_trip_transparency() {
+    let image = bitmap(
+        5,
+        8,
+        |x, y| if (x + y) % 3 == 0 { TRANSPARENT } else { GREY },
+    );
+    let sixel = encode_to_string(&Encoder::new(), &image);
+    assert!(sixel.starts_with("\u{1b}P0;1q"));
+    assert_eq!(round_trip(&Encoder::new(), &image), image);
+}
+
+#[test]
+fn alpha_threshold() {
+    let image = bitmap(2, 1, |x, _| [255, 0, 0, if x == 0 { 100 } else { 200 }]);
+    let encoder = Encoder::new();
+    let decoded = round_trip(&encoder, &image);
+    assert_eq!(decoded.pixel(0, 0), TRANSPARENT);
+    assert_eq!(decoded.pixel(1, 0), RED);
+    let opaque = Encoder {
+        alpha_threshold: None,
+        ..Encoder::new()
+    };
+    let decoded = round_trip(&opaque, &image);
+    assert_eq!(decoded.pixel(0, 0), RED);
+    assert!(!encode_to_string(&opaque, &image).starts_with("\u{1b}P0;1q"));
+}
+
+#[test]
+fn colors_are_rounded_to_percentages() {
+    let image = bitmap(1, 1, |_, _| [1, 254, 130, 255]);
+    let decoded = round_trip(&Encoder::new(), &image);
+    assert_eq!(decoded.pixel(0, 0), [0, 255, 130, 255]);
+}
+
+#[test]
+fn palette_is_reduced() {
+    let image = bitmap(64, 64, |x, y| [(x * 4) as u8, (y * 4) as u8, 128, 255]);
+    for dithering in [Dithering::None, Dithering::FloydSteinberg] {
+        let encoder = Encoder {
+            max_colors: 16,
+            dithering,
+            ..Encoder::new()
+        };
+        let decoded = round_trip(&encoder, &image);
+        let mut colors: Vec<_> = decoded.pixels.chunks_exact(4).collect();
+        colors.sort_unstable();
+        colors.dedup();
+        assert!(colors.len() <= 16);
+        // the average error stays small
+        let error: usize = image
+            .pixels
+            .iter()
+            .zip(&decoded.pixels)
+            .map(|(a, b)| a.abs_diff(*b) as usize)
+            .sum();
+        assert!(
+            error / image.pixels.len() < 16,
+            "{:?}: {}",
+            dithering,
+            error
+        );
+    }
+}
+
+#[test]
+fn single_color_palette() {
+    let image = bitmap(3, 3, |x, _| if x == 0 { RED } else { GREEN });
+    let encoder = Encoder {
+        max_colors: 1,
+        ..Encoder::new()
+    };
+    let decoded = round_trip(&encoder, &image);
+    assert_eq!(decoded.pixel(0, 0), decoded.pixel(2, 2));
+}
+
+#[test]
+fn aspect_ratio_is_kept() {
+    let mut image = bitmap(2, 2, |_, _| GREY);
+    image.pixel_aspect_ratio = (2, 1);
+    assert_eq!(round_trip(&Encoder::new(), &image), image);
+}
+
+#[test]
+fn empty_bitmap() {
+    let image = Bitmap::new(0, 0);
+    assert_eq!(
+        encode_to_string(&Encoder::new(), &image),
+        "\u{1b}Pq\"1;1;0;0\u{1b}\\"
+    );
+    assert_eq!(round_trip(&Encoder::new(), &image), image);
+}
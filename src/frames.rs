@@ -0,0 +1,195 @@
+//! Groups the images of a stream into frames, for animations drawn as a sequence of sixel images.
+//!
+//! Each frame holds the events of one image (from its `Dcs` to its `End`), its byte span in the
+//! input, its raster attributes and the color registers it defines. Events between images (such
+//! as the cursor movement of an animation) are not part of any frame.
+//!
+//! Terminals either give each image its own color registers or share them between images (see
+//! [`RegisterMode`]). With shared registers, the palette of each frame starts out as the palette
+//! the previous frame ended with.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::{FrameSplitter, RegisterMode};
+//!
+//! let stream = b"\x1bPq#1;2;100;0;0#1~\x1b\\\x1b[H\x1bPq#1~\x1b\\";
+//! let mut frames = vec![];
+//! let mut splitter = FrameSplitter::new();
+//! splitter.set_register_mode(RegisterMode::Shared);
+//! for byte in stream {
+//!     splitter.advance(byte, |frame| frames.push(frame));
+//! }
+//! splitter.finish(|frame| frames.push(frame));
+//!
+//! assert_eq!(frames.len(), 2);
+//! assert_eq!(frames[1].span, 23..31);
+//! assert!(frames[1].palette_delta.is_empty());
+//! // register 1 is still red
+//! assert_eq!(frames[1].palette.get(1), [255, 0, 0, 255]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::{ColorCoordinateSystem, Limits, Palette, RegisterMode, SixelEvent, SpannedParser};
+
+/// The raster attributes of a frame (`" Pan ; Pad ; Ph ; Pv`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Raster {
+    pub pan: usize,
+    pub pad: usize,
+    pub ph: Option<usize>,
+    pub pv: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The position of this frame in the stream, counting from 0.
+    pub index: usize,
+    /// The events of the image, from its `Dcs` event to its `End` (or `Unterminated`) event.
+    pub events: Vec<SixelEvent>,
+    /// The bytes of the input the image was parsed from.
+    pub span: Range<usize>,
+    /// The last raster attributes of the image, if it has any.
+    pub raster: Option<Raster>,
+    /// The color registers the image defines, with their last definition, ordered by register.
+    pub palette_delta: Vec<(u16, ColorCoordinateSystem)>,
+    /// The color registers at the end of the image.
+    pub palette: Palette,
+    /// Whether the image was ended by its string terminator.
+    pub terminated: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameSplitter {
+    parser: SpannedParser,
+    frames: FrameState,
+}
+
+#[derive(Clone, Debug)]
+struct FrameState {
+    frame: Option<Frame>,
+    palette_delta: BTreeMap<u16, ColorCoordinateSystem>,
+    // the palette the previous frame ended with
+    palette: Palette,
+    register_mode: RegisterMode,
+    count: usize,
+}
+
+impl Default for FrameSplitter {
+    fn default() -> Self {
+        FrameSplitter::new()
+    }
+}
+
+impl FrameSplitter {
+    /// Creates a splitter whose palettes have the default number of color registers (see
+    /// [`Limits`]) and start out as the VT340's default palette in every frame.
+    pub fn new() -> Self {
+        FrameSplitter::with_color_registers(Limits::default().color_registers)
+    }
+    pub fn with_color_registers(color_registers: usize) -> Self {
+        FrameSplitter {
+            parser: SpannedParser::new(),
+            frames: FrameState {
+                frame: None,
+                palette_delta: BTreeMap::new(),
+                palette: Palette::vt340(color_registers),
+                register_mode: RegisterMode::Private,
+                count: 0,
+            },
+        }
+    }
+    /// Sets whether color registers keep their colors from one frame to the next.
+    pub fn set_register_mode(&mut self, register_mode: RegisterMode) {
+        self.frames.register_mode = register_mode;
+    }
+    /// Resets the shared color registers to the default palette.
+    pub fn reset_palette(&mut self) {
+        self.frames.palette = Palette::vt340(self.frames.palette.len());
+    }
+    /// Feeds a single byte to the splitter. Once an image ends, its frame is passed to the
+    /// callback.
+    pub fn advance(&mut self, byte: &u8, mut cb: impl FnMut(Frame)) {
+        let frames = &mut self.frames;
+        self.parser.advance(byte, |event, span| {
+            frames.handle_event(event, span, &mut cb)
+        });
+    }
+    /// Signals the end of the input. An image that was not ended by its string terminator is
+    /// passed to the callback as an unterminated frame.
+    pub fn finish(&mut self, mut cb: impl FnMut(Frame)) {
+        let frames = &mut self.frames;
+        self.parser
+            .finish(|event, span| frames.handle_event(event, span, &mut cb));
+    }
+}
+
+impl FrameState {
+    fn handle_event(&mut self, event: SixelEvent, span: Range<usize>, cb: &mut impl FnMut(Frame)) {
+        if let SixelEvent::Dcs { .. } = event {
+            // an image that is interrupted by another one
+            self.emit_frame(false, cb);
+            if self.register_mode == RegisterMode::Private {
+                self.palette = Palette::vt340(self.palette.len());
+            }
+            self.frame = Some(Frame {
+                index: self.count,
+                events: vec![],
+                span: span.clone(),
+                raster: None,
+                palette_delta: vec![],
+                palette: self.palette.clone(),
+                terminated: false,
+            });
+            self.count += 1;
+        }
+        let frame = match self.frame.as_mut() {
+            Some(frame) => frame,
+            None => return,
+        };
+        frame.events.push(event);
+        frame.span.end = span.end;
+        match event {
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                frame.raster = Some(Raster { pan, pad, ph, pv });
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: Some(color),
+            } => {
+                frame.palette.set(color_number, color);
+                self.palette_delta.insert(color_number, color);
+            }
+            SixelEvent::End => self.emit_frame(true, cb),
+            SixelEvent::Unterminated => self.emit_frame(false, cb),
+            _ => {}
+        }
+    }
+    fn emit_frame(&mut self, terminated: bool, cb: &mut impl FnMut(Frame)) {
+        if let Some(mut frame) = self.frame.take() {
+            frame.terminated = terminated;
+            frame.palette_delta = std::mem::take(&mut self.palette_delta)
+                .into_iter()
+                .collect();
+            self.palette = frame.palette.clone();
+            cb(frame);
+        }
+    }
+}
+
+/// Splits a buffer of sixel bytes into frames.
+pub fn split(bytes: &[u8], register_mode: RegisterMode) -> Vec<Frame> {
+    let mut frames = vec![];
+    let mut splitter = FrameSplitter::new();
+    splitter.set_register_mode(register_mode);
+    for byte in bytes {
+        splitter.advance(byte, |frame| frames.push(frame));
+    }
+    splitter.finish(|frame| frames.push(frame));
+    frames
+}
+
+#[cfg(test)]
+#[path = "./frames_tests.rs"]
+mod tests;
@@ -0,0 +1,117 @@
+use super::*;
+
+const RED: ColorCoordinateSystem = ColorCoordinateSystem::RGB(100, 0, 0);
+const BLUE: ColorCoordinateSystem = ColorCoordinateSystem::RGB(0, 0, 100);
+
+#[test]
+fn frames_with_spans() {
+    let stream = b"\x1b[H\x1bPq\"1;1;2;6#1;2;100;0;0#1~~\x1b\\\x1b[H\x1bPq#2~\x1b\\";
+    let frames = split(stream, RegisterMode::Private);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].index, 0);
+    assert_eq!(frames[0].span, 3..32);
+    assert_eq!(&stream[frames[0].span.clone()], &stream[3..32]);
+    assert_eq!(
+        frames[0].events.first(),
+        Some(&SixelEvent::new_dcs(None, None, None))
+    );
+    assert_eq!(frames[0].events.last(), Some(&SixelEvent::End));
+    assert_eq!(
+        frames[0].raster,
+        Some(Raster {
+            pan: 1,
+            pad: 1,
+            ph: Some(2),
+            pv: Some(6)
+        })
+    );
+    assert_eq!(frames[1].index, 1);
+    assert_eq!(&stream[frames[1].span.clone()], b"\x1bPq#2~\x1b\\");
+    assert_eq!(frames[1].raster, None);
+    assert!(frames.iter().all(|frame| frame.terminated));
+}
+
+#[test]
+fn palette_delta_keeps_the_last_definition() {
+    let frames = split(
+        b"\x1bPq#3;2;0;0;100#1;2;0;0;100#1;2;100;0;0#3~\x1b\\",
+        RegisterMode::Private,
+    );
+    assert_eq!(frames[0].palette_delta, vec![(1, RED), (3, BLUE)]);
+    assert_eq!(frames[0].palette.get(1), [255, 0, 0, 255]);
+    assert_eq!(frames[0].palette.get(3), [0, 0, 255, 255]);
+}
+
+#[test]
+fn private_palettes() {
+    let frames = split(
+        b"\x1bPq#1;2;100;0;0\x1b\\\x1bPq#1~\x1b\\",
+        RegisterMode::Private,
+    );
+    assert_eq!(frames[1].palette_delta, vec![]);
+    assert_eq!(
+        frames[1].palette,
+        Palette::vt340(Limits::default().color_registers)
+    );
+}
+
+#[test]
+fn shared_palette() {
+    let frames = split(
+        b"\x1bPq#1;2;100;0;0\x1b\\\x1bPq#2;2;0;0;100\x1b\\\x1bPq#1;2;0;0;100\x1b\\",
+        RegisterMode::Shared,
+    );
+    assert_eq!(frames[1].palette_delta, vec![(2, BLUE)]);
+    assert_eq!(frames[1].palette.get(1), [255, 0, 0, 255]);
+    assert_eq!(frames[1].palette.get(2), [0, 0, 255, 255]);
+    assert_eq!(frames[2].palette.get(1), [0, 0, 255, 255]);
+}
+
+#[test]
+fn color_registers() {
+    let mut splitter = FrameSplitter::with_color_registers(16);
+    let mut frames = vec![];
+    for byte in b"\x1bPq#17;2;100;0;0\x1b\\" {
+        splitter.advance(byte, |frame| frames.push(frame));
+    }
+    assert_eq!(frames[0].palette.len(), 16);
+    assert_eq!(frames[0].palette.get(1), [255, 0, 0, 255]);
+}
+
+#[test]
+fn unterminated_frame() {
+    let frames = split(b"\x1bPq#1~", RegisterMode::Private);
+    assert_eq!(frames.len(), 1);
+    assert!(!frames[0].terminated);
+    assert_eq!(frames[0].events.last(), Some(&SixelEvent::Unterminated));
+    assert_eq!(frames[0].span, 0..6);
+}
+
+#[test]
+fn interrupted_frame() {
+    let frames = split(b"\x1bPq#1~\x1bPq~\x1b\\", RegisterMode::Private);
+    assert_eq!(frames.len(), 2);
+    assert!(!frames[0].terminated);
+    assert_eq!(frames[0].span, 0..6);
+    assert!(frames[1].terminated);
+}
+
+#[test]
+fn no_frames() {
+    assert_eq!(split(b"~~-#1;2;0;0;0\x1b\\", RegisterMode::Private), vec![]);
+}
+
+#[test]
+fn reset_shared_palette() {
+    let mut splitter = FrameSplitter::new();
+    splitter.set_register_mode(RegisterMode::Shared);
+    let mut frames = vec![];
+    for byte in b"\x1bPq#1;2;100;0;0\x1b\\" {
+        splitter.advance(byte, |frame| frames.push(frame));
+    }
+    splitter.reset_palette();
+    for byte in b"\x1bPq#1~\x1b\\" {
+        splitter.advance(byte, |frame| frames.push(frame));
+    }
+    assert_eq!(frames[1].palette.get(1), [51, 51, 204, 255]);
+}
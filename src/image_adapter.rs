@@ -0,0 +1,132 @@
+//! Integration with the [`image`](::image) crate.
+//!
+//! A [`Bitmap`] converts to and from `image`'s buffers, so decoded sixels can be processed or
+//! saved with `image` and any image it can open can be encoded:
+//! ```rust
+//! use image::{DynamicImage, RgbaImage};
+//! use sixel_tokenizer::decoder::decode;
+//! use sixel_tokenizer::{Bitmap, Encoder};
+//!
+//! let decoded = decode(b"\x1bPq\"1;1;2;1#1;2;100;0;0~~\x1b\\").remove(0);
+//! let image = RgbaImage::try_from(decoded).unwrap();
+//! assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0, 255]);
+//!
+//! let mut sixel = vec![];
+//! let image = DynamicImage::ImageRgba8(image);
+//! Encoder::new().encode_to(&Bitmap::from(&image), &mut sixel).unwrap();
+//! assert_eq!(decode(&sixel)[0].pixel(1, 0), [255, 0, 0, 255]);
+//! ```
+//!
+//! [`register_image_format`] lets `image::open` and `ImageReader` read `.six` and `.sixel` files
+//! through [`SixelImageDecoder`].
+
+use std::ffi::OsString;
+use std::io::Read;
+
+use ::image::error::{
+    DecodingError, ImageError, ImageFormatHint, ImageResult, LimitError, LimitErrorKind,
+};
+use ::image::hooks::{register_decoding_hook, register_format_detection_hook};
+use ::image::{ColorType, DynamicImage, ImageDecoder, RgbaImage};
+
+use crate::decoder::decode_with;
+use crate::{Bitmap, Decoder};
+
+impl TryFrom<Bitmap> for RgbaImage {
+    type Error = ImageError;
+    /// Fails if the bitmap is too large for `image`. The pixel aspect ratio is not kept, see
+    /// [`Bitmap::correct_aspect_ratio`].
+    fn try_from(bitmap: Bitmap) -> ImageResult<RgbaImage> {
+        let too_large =
+            || ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError));
+        let width = u32::try_from(bitmap.width).map_err(|_| too_large())?;
+        let height = u32::try_from(bitmap.height).map_err(|_| too_large())?;
+        RgbaImage::from_raw(width, height, bitmap.pixels).ok_or_else(too_large)
+    }
+}
+
+impl From<RgbaImage> for Bitmap {
+    fn from(image: RgbaImage) -> Self {
+        let (width, height) = image.dimensions();
+        Bitmap::from_rgba(width as usize, height as usize, image.into_raw())
+    }
+}
+
+impl From<&DynamicImage> for Bitmap {
+    fn from(image: &DynamicImage) -> Self {
+        Bitmap::from(image.to_rgba8())
+    }
+}
+
+/// An `image` decoder for the first sixel image in its input.
+pub struct SixelImageDecoder {
+    bitmap: Bitmap,
+}
+
+impl SixelImageDecoder {
+    /// Reads and decodes all of `reader` with a [`Decoder`] with the default limits.
+    pub fn new(reader: impl Read) -> ImageResult<Self> {
+        SixelImageDecoder::with_decoder(reader, Decoder::new())
+    }
+    pub fn with_decoder(mut reader: impl Read, decoder: Decoder) -> ImageResult<Self> {
+        let mut bytes = vec![];
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(ImageError::IoError)?;
+        let mut bitmap = None;
+        decode_with(decoder, &bytes, |image| {
+            bitmap.get_or_insert(image);
+        });
+        match bitmap {
+            Some(bitmap)
+                if u32::try_from(bitmap.width).is_ok() && u32::try_from(bitmap.height).is_ok() =>
+            {
+                Ok(SixelImageDecoder { bitmap })
+            }
+            Some(_) => Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::DimensionError,
+            ))),
+            None => Err(ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Name("sixel".to_owned()),
+                "no sixel image in the input",
+            ))),
+        }
+    }
+}
+
+impl ImageDecoder for SixelImageDecoder {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.bitmap.width as u32, self.bitmap.height as u32)
+    }
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        buf.copy_from_slice(&self.bitmap.pixels);
+        Ok(())
+    }
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+/// Registers [`SixelImageDecoder`] with `image` for the `six` and `sixel` extensions, and for
+/// files starting with a DCS. Returns false if decoders for these extensions were already
+/// registered.
+pub fn register_image_format() -> bool {
+    let mut registered = true;
+    for extension in ["six", "sixel"] {
+        registered &= register_decoding_hook(
+            OsString::from(extension),
+            Box::new(|reader| Ok(Box::new(SixelImageDecoder::new(reader)?))),
+        );
+    }
+    if registered {
+        register_format_detection_hook(OsString::from("six"), b"\x1bP", None);
+    }
+    registered
+}
+
+#[cfg(test)]
+#[path = "./image_adapter_tests.rs"]
+mod tests;
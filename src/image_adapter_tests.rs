@@ -0,0 +1,72 @@
+use super::*;
+use std::io::Cursor;
+
+use ::image::{ImageReader, Rgba};
+
+const SIXEL: &[u8] = b"\x1bP0;1q\"1;1;3;2#1;2;100;0;0B@\x1b\\";
+
+#[test]
+fn bitmap_to_rgba_image() {
+    let mut bitmap = Bitmap::new(3, 2);
+    bitmap.set_pixel(2, 1, [1, 2, 3, 4]);
+    let image = RgbaImage::try_from(bitmap.clone()).unwrap();
+    assert_eq!(image.dimensions(), (3, 2));
+    assert_eq!(*image.get_pixel(2, 1), Rgba([1, 2, 3, 4]));
+    assert_eq!(Bitmap::from(image), bitmap);
+}
+
+#[test]
+fn oversized_bitmap() {
+    let bitmap = Bitmap::new(u32::MAX as usize + 1, 0);
+    assert!(matches!(
+        RgbaImage::try_from(bitmap),
+        Err(ImageError::Limits(_))
+    ));
+}
+
+#[test]
+fn dynamic_image_to_bitmap() {
+    let image = DynamicImage::ImageLuma8(::image::GrayImage::from_raw(2, 1, vec![0, 255]).unwrap());
+    let bitmap = Bitmap::from(&image);
+    assert_eq!(bitmap.pixels, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+}
+
+#[test]
+fn image_decoder() {
+    let decoder = SixelImageDecoder::new(SIXEL).unwrap();
+    assert_eq!(decoder.dimensions(), (3, 2));
+    let image = DynamicImage::from_decoder(decoder).unwrap().to_rgba8();
+    assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*image.get_pixel(1, 0), Rgba([255, 0, 0, 255]));
+    assert_eq!(*image.get_pixel(1, 1), Rgba([0, 0, 0, 0]));
+}
+
+#[test]
+fn image_decoder_without_image() {
+    assert!(matches!(
+        SixelImageDecoder::new(&b"no image"[..]),
+        Err(ImageError::Decoding(_))
+    ));
+}
+
+#[test]
+fn registered_format() {
+    register_image_format();
+    assert!(!register_image_format());
+
+    let image = ImageReader::new(Cursor::new(SIXEL))
+        .with_guessed_format()
+        .unwrap()
+        .decode()
+        .unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+
+    let path = std::env::temp_dir().join(format!("sixel-tokenizer-{}.sixel", std::process::id()));
+    std::fs::write(&path, SIXEL).unwrap();
+    let image = ::image::open(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        *image.unwrap().to_rgba8().get_pixel(0, 1),
+        Rgba([255, 0, 0, 255])
+    );
+}
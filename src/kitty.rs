@@ -0,0 +1,174 @@
+//! Transcodes sixel images to the kitty graphics protocol, for terminals (such as kitty and
+//! WezTerm) that prefer it to sixel.
+//!
+//! Each image is sent as 32-bit RGBA pixels (`f=32`) that the terminal transmits and displays
+//! right away (`a=T`). The base64 encoded pixels are split into chunks of at most
+//! [`MAX_CHUNK_SIZE`] bytes, each in its own `ESC _G ... ESC \` sequence: all chunks but the last
+//! have `m=1`, the last has `m=0`. Only the first chunk has the other keys.
+//!
+//! As with the [`Encoder`](crate::Encoder), the pixel aspect ratio of a bitmap is not kept (see
+//! [`Bitmap::correct_aspect_ratio`]).
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::kitty::transcode;
+//!
+//! let kitty = transcode(b"\x1bPq\"1;1;1;1#1;2;100;0;0#1@\x1b\\");
+//! assert_eq!(kitty, b"\x1b_Ga=T,f=32,s=1,v=1,q=2,m=0;/wAA/w==\x1b\\");
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{Bitmap, Decoder, Parser, SixelEvent, SliceEvent};
+
+/// The largest number of base64 bytes in one escape sequence.
+pub const MAX_CHUNK_SIZE: usize = 4096;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KittyEncoder {
+    /// The image id (`i`). Without one, the terminal assigns an id that it doesn't report.
+    pub image_id: Option<u32>,
+    /// Whether the cursor moves past the image, as it does after a sixel image (`C=0`), or stays
+    /// where it is (`C=1`).
+    pub move_cursor: bool,
+    /// Whether the terminal is asked not to respond (`q=2`).
+    pub quiet: bool,
+}
+
+impl Default for KittyEncoder {
+    fn default() -> Self {
+        KittyEncoder {
+            image_id: None,
+            move_cursor: true,
+            quiet: true,
+        }
+    }
+}
+
+impl KittyEncoder {
+    pub fn new() -> Self {
+        KittyEncoder::default()
+    }
+    /// Passes each escape sequence of the image to the callback. An empty bitmap has none.
+    pub fn encode(&self, bitmap: &Bitmap, mut cb: impl FnMut(&[u8])) {
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return;
+        }
+        // every chunk but the last is a whole number of base64 groups
+        let chunks = bitmap.pixels.chunks(MAX_CHUNK_SIZE / 4 * 3);
+        let count = chunks.len();
+        let mut sequence = Vec::with_capacity(MAX_CHUNK_SIZE + 64);
+        for (i, chunk) in chunks.enumerate() {
+            sequence.clear();
+            sequence.extend_from_slice(b"\x1b_G");
+            if i == 0 {
+                self.write_keys(bitmap, &mut sequence);
+            }
+            let more = if i + 1 < count { b'1' } else { b'0' };
+            sequence.extend_from_slice(&[b'm', b'=', more, b';']);
+            encode_base64(chunk, &mut sequence);
+            sequence.extend_from_slice(b"\x1b\\");
+            cb(&sequence);
+        }
+    }
+    /// Writes the escape sequences of the image.
+    pub fn encode_to(&self, bitmap: &Bitmap, writer: &mut impl Write) -> io::Result<()> {
+        let mut result = Ok(());
+        self.encode(bitmap, |sequence| {
+            if result.is_ok() {
+                result = writer.write_all(sequence);
+            }
+        });
+        result
+    }
+    fn write_keys(&self, bitmap: &Bitmap, sequence: &mut Vec<u8>) {
+        // writing to a Vec can't fail
+        let _ = write!(sequence, "a=T,f=32,s={},v={},", bitmap.width, bitmap.height);
+        if let Some(image_id) = self.image_id {
+            let _ = write!(sequence, "i={},", image_id);
+        }
+        if self.quiet {
+            sequence.extend_from_slice(b"q=2,");
+        }
+        if !self.move_cursor {
+            sequence.extend_from_slice(b"C=1,");
+        }
+    }
+}
+
+/// Decodes sixel images from a stream of [`SixelEvent`]s and encodes each of them with a
+/// [`KittyEncoder`].
+#[derive(Clone, Debug)]
+pub struct KittyTranscoder {
+    decoder: Decoder,
+    encoder: KittyEncoder,
+}
+
+impl Default for KittyTranscoder {
+    fn default() -> Self {
+        KittyTranscoder::new()
+    }
+}
+
+impl KittyTranscoder {
+    pub fn new() -> Self {
+        KittyTranscoder::with_encoder(Decoder::new(), KittyEncoder::new())
+    }
+    pub fn with_encoder(decoder: Decoder, encoder: KittyEncoder) -> Self {
+        KittyTranscoder { decoder, encoder }
+    }
+    /// Feeds a single event to the transcoder. Once an image ends, its escape sequences are
+    /// passed to the callback.
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(&[u8])) {
+        let encoder = &self.encoder;
+        self.decoder
+            .advance(event, |bitmap| encoder.encode(&bitmap, &mut cb));
+    }
+    /// Signals the end of the input, an image that was not ended is transcoded as it is.
+    pub fn finish(&mut self, mut cb: impl FnMut(&[u8])) {
+        let encoder = &self.encoder;
+        self.decoder
+            .finish(|bitmap| encoder.encode(&bitmap, &mut cb));
+    }
+}
+
+/// Transcodes every image in a buffer of sixel bytes with the default decoder and encoder.
+pub fn transcode(bytes: &[u8]) -> Vec<u8> {
+    let mut kitty = vec![];
+    let mut transcoder = KittyTranscoder::new();
+    let mut parser = Parser::new();
+    let mut cb = |sequence: &[u8]| kitty.extend_from_slice(sequence);
+    parser.advance_slice(bytes, |event| match event {
+        SliceEvent::DataRun(run) => {
+            for byte in run {
+                transcoder.advance(SixelEvent::Data { byte: *byte }, &mut cb);
+            }
+        }
+        SliceEvent::Event(event) => transcoder.advance(event, &mut cb),
+    });
+    parser.finish(|event| transcoder.advance(event, &mut cb));
+    transcoder.finish(&mut cb);
+    kitty
+}
+
+fn encode_base64(bytes: &[u8], output: &mut Vec<u8>) {
+    for group in bytes.chunks(3) {
+        let n = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                output.push(BASE64[(n >> (18 - 6 * i)) as usize & 0b111111]);
+            } else {
+                output.push(b'=');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./kitty_tests.rs"]
+mod tests;
@@ -0,0 +1,100 @@
+use super::*;
+
+fn sequences(encoder: &KittyEncoder, bitmap: &Bitmap) -> Vec<Vec<u8>> {
+    let mut sequences = vec![];
+    encoder.encode(bitmap, |sequence| sequences.push(sequence.to_vec()));
+    sequences
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut output = vec![];
+    encode_base64(bytes, &mut output);
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn base64_padding() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+}
+
+#[test]
+fn single_chunk() {
+    let bitmap = Bitmap::from_rgba(2, 1, vec![0, 0, 0, 0, 255, 255, 255, 255]);
+    let sequences = sequences(&KittyEncoder::new(), &bitmap);
+    assert_eq!(
+        sequences,
+        vec![b"\x1b_Ga=T,f=32,s=2,v=1,q=2,m=0;AAAAAP////8=\x1b\\".to_vec()]
+    );
+}
+
+#[test]
+fn large_images_are_chunked() {
+    // 8192 bytes of pixels are 10924 bytes of base64
+    let bitmap = Bitmap::from_rgba(64, 32, vec![7; 64 * 32 * 4]);
+    let sequences = sequences(&KittyEncoder::new(), &bitmap);
+    assert_eq!(sequences.len(), 3);
+    let prefix = |sequence: &[u8], prefix: &[u8]| sequence.starts_with(prefix);
+    assert!(prefix(&sequences[0], b"\x1b_Ga=T,f=32,s=64,v=32,q=2,m=1;"));
+    assert!(prefix(&sequences[1], b"\x1b_Gm=1;"));
+    assert!(prefix(&sequences[2], b"\x1b_Gm=0;"));
+    let mut payload = vec![];
+    for sequence in &sequences {
+        assert!(sequence.ends_with(b"\x1b\\"));
+        let start = sequence.iter().position(|byte| *byte == b';').unwrap() + 1;
+        let chunk = &sequence[start..sequence.len() - 2];
+        assert!(chunk.len() <= MAX_CHUNK_SIZE);
+        payload.extend_from_slice(chunk);
+    }
+    assert_eq!(payload.len(), 10924);
+    assert_eq!(String::from_utf8(payload).unwrap(), base64(&bitmap.pixels));
+}
+
+#[test]
+fn encoder_keys() {
+    let encoder = KittyEncoder {
+        image_id: Some(42),
+        move_cursor: false,
+        quiet: false,
+    };
+    let bitmap = Bitmap::from_rgba(1, 1, vec![1, 2, 3, 4]);
+    let mut kitty = vec![];
+    encoder.encode_to(&bitmap, &mut kitty).unwrap();
+    assert_eq!(kitty, b"\x1b_Ga=T,f=32,s=1,v=1,i=42,C=1,m=0;AQIDBA==\x1b\\");
+}
+
+#[test]
+fn empty_bitmap_has_no_sequences() {
+    let bitmap = Bitmap::from_rgba(0, 0, vec![]);
+    assert!(sequences(&KittyEncoder::new(), &bitmap).is_empty());
+}
+
+#[test]
+fn transcode_every_image() {
+    let kitty = transcode(b"\x1bPq#1;2;0;0;100#1@\x1b\\text\x1bPq#1;2;0;100;0#1@");
+    let blue = b"\x1b_Ga=T,f=32,s=1,v=1,q=2,m=0;AAD//w==\x1b\\";
+    let green = b"\x1b_Ga=T,f=32,s=1,v=1,q=2,m=0;AP8A/w==\x1b\\";
+    assert_eq!(kitty, [&blue[..], &green[..]].concat());
+}
+
+#[test]
+fn transcoder_emits_each_image_when_it_ends() {
+    let mut transcoder = KittyTranscoder::new();
+    let mut count = 0;
+    let mut parser = Parser::new();
+    for byte in b"\x1bPq#1@\x1b\\" {
+        parser.advance(byte, |event| {
+            transcoder.advance(event, |sequence| {
+                assert!(sequence.starts_with(b"\x1b_G"));
+                count += 1;
+            })
+        });
+    }
+    assert_eq!(count, 1);
+    transcoder.finish(|_| count += 1);
+    assert_eq!(count, 1);
+}
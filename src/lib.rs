@@ -54,9 +54,49 @@ use std::str::Utf8Error;
 use arrayvec::{ArrayVec, CapacityError};
 use thiserror::Error;
 
+mod bitmap;
+mod crop;
+pub mod decoder;
+pub mod demultiplexer;
+pub mod encoder;
+pub mod frames;
+pub mod kitty;
+#[cfg(feature = "image")]
+mod image_adapter;
+pub mod lint;
+mod optimizer;
+mod palette;
+pub mod placement;
+pub mod preview;
+pub mod query;
+pub mod scanner;
 mod sixel_event;
+mod soft_font;
+mod spanned_parser;
+mod stats;
+mod tiler;
+#[cfg(feature = "vte")]
+mod vte_adapter;
 pub use sixel_event::SixelEvent;
+pub use sixel_event::SliceEvent;
 pub use sixel_event::ColorCoordinateSystem;
+pub use soft_font::{SoftFontEvent, SoftFontParser};
+pub use bitmap::Bitmap;
+pub use crop::Crop;
+pub use decoder::{Decoder, Limits};
+pub use encoder::{Dithering, Encoder};
+pub use frames::{Frame, FrameSplitter, Raster};
+pub use kitty::{KittyEncoder, KittyTranscoder};
+pub use optimizer::Optimizer;
+pub use palette::{Palette, RegisterMode};
+pub use preview::PreviewStyle;
+pub use spanned_parser::SpannedParser;
+pub use stats::Stats;
+pub use tiler::{Tile, Tiler};
+#[cfg(feature = "image")]
+pub use image_adapter::{register_image_format, SixelImageDecoder};
+#[cfg(feature = "vte")]
+pub use vte_adapter::VteAdapter;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
@@ -72,7 +112,8 @@ pub enum ParserError {
     ParseIntError(#[from] ParseIntError),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParserState {
     Ground,
     DeviceControlString,
@@ -81,14 +122,28 @@ pub enum ParserState {
     RasterAttribute,
     GraphicsRepeatIntroducer,
     UnknownSequence,
+    SoftFontCharset,
+    SoftFont,
 }
 
+/// With the `serde` feature, a parser can be serialized at any point of its input. A deserialized
+/// parser continues where the original one left off.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parser {
     state: ParserState,
     raw_instruction: ArrayVec<u8, 256>,
-    pending_event_fields: ArrayVec<ArrayVec<u8, 5>, 5>,
+    pending_event_fields: ArrayVec<ArrayVec<u8, 5>, 8>,
     currently_parsing: ArrayVec<u8, 256>,
+    pending_dcs_event: Option<SixelEvent>,
+    // whether a `Dcs` event was emitted and its `End` was not
+    in_image: bool,
+    // parsers created with `new_in_body` have their string terminator consumed elsewhere
+    in_body: bool,
+    // the `Dscs` bytes of a soft font's introducer
+    charset: ArrayVec<u8, 3>,
+    // whether a `SoftFontEvent::Dld` event was emitted and its `End` was not
+    in_soft_font: bool,
 }
 
 impl Parser {
@@ -98,9 +153,50 @@ impl Parser {
             raw_instruction: ArrayVec::new(),
             pending_event_fields: ArrayVec::new(),
             currently_parsing: ArrayVec::new(),
+            pending_dcs_event: None,
+            in_image: false,
+            in_body: false,
+            charset: ArrayVec::new(),
+            in_soft_font: false,
         }
     }
-    pub fn advance(&mut self, byte: &u8, mut cb: impl FnMut(SixelEvent)) {
+    /// Creates a parser for the body of a sixel image whose DCS introducer (`ESC P ... q`) was
+    /// already consumed. The `Dcs` event with the given parameters is emitted before the first
+    /// event of the body, and [`Parser::finish`] ends the image with `End`.
+    pub fn new_in_body(
+        macro_parameter: Option<u8>,
+        transparent_background: Option<u8>,
+        horizontal_pixel_distance: Option<usize>,
+    ) -> Self {
+        let mut parser = Parser::new();
+        parser.pending_dcs_event = Some(SixelEvent::new_dcs(
+            macro_parameter,
+            transparent_background,
+            horizontal_pixel_distance,
+        ));
+        parser.in_image = true;
+        parser.in_body = true;
+        parser
+    }
+    /// Feeds a single byte to the parser. DECDLD soft fonts are skipped, see
+    /// [`Parser::advance_with_soft_fonts`].
+    pub fn advance(&mut self, byte: &u8, cb: impl FnMut(SixelEvent)) {
+        self.advance_with_soft_fonts(byte, cb, |_| {});
+    }
+    /// Like [`Parser::advance`], except that the introducer and glyphs of DECDLD soft fonts are
+    /// passed to `font_cb` as [`SoftFontEvent`]s.
+    pub fn advance_with_soft_fonts(
+        &mut self,
+        byte: &u8,
+        mut cb: impl FnMut(SixelEvent),
+        mut font_cb: impl FnMut(SoftFontEvent),
+    ) {
+        if let Some(dcs_event) = self.pending_dcs_event.take() {
+            cb(dcs_event);
+        }
+        if self.advance_soft_font(*byte, &mut cb, &mut font_cb) {
+            return;
+        }
         if byte == &b' ' || byte == &b'\n' || byte == &b'\t' {
             // ignore whitespace
             return;
@@ -109,6 +205,72 @@ impl Parser {
             self.handle_error(e, Some(*byte), &mut cb);
         }
     }
+    /// Like calling [`Parser::advance`] with each of `bytes`, except that consecutive sixel data
+    /// bytes are reported as a single [`SliceEvent::DataRun`] rather than as one `Data` event
+    /// each. Runs end at any other byte (including whitespace) and at the end of `bytes`.
+    pub fn advance_slice<'a>(&mut self, bytes: &'a [u8], mut cb: impl FnMut(SliceEvent<'a>)) {
+        let mut i = 0;
+        while i < bytes.len() {
+            let run_start = i;
+            if !self.is_between_instructions() || !is_sixel_data(bytes[i]) {
+                // a data byte that ends a pending instruction still starts a run
+                let mut starts_run = false;
+                self.advance(&bytes[i], |event| match event {
+                    SixelEvent::Data { .. } => starts_run = true,
+                    _ => cb(SliceEvent::Event(event)),
+                });
+                i += 1;
+                if !starts_run {
+                    continue;
+                }
+            }
+            i += scanner::data_run_length(&bytes[i..]);
+            cb(SliceEvent::DataRun(&bytes[run_start..i]));
+        }
+    }
+    /// Signals the end of the input and resets the parser.
+    ///
+    /// A pending `ColorIntroducer` or `RasterAttribute` event is emitted, and an incomplete
+    /// instruction is reported as an `UnknownSequence`. An image that was not ended by its string
+    /// terminator (`ESC \`) is then ended with `End` if this parser was created with
+    /// [`Parser::new_in_body`], or with `Unterminated` otherwise.
+    pub fn finish(&mut self, cb: impl FnMut(SixelEvent)) {
+        self.finish_with_soft_fonts(cb, |_| {});
+    }
+    /// Like [`Parser::finish`], except that a soft font that was not ended by its string
+    /// terminator is ended with [`SoftFontEvent::Unterminated`].
+    pub fn finish_with_soft_fonts(
+        &mut self,
+        mut cb: impl FnMut(SixelEvent),
+        mut font_cb: impl FnMut(SoftFontEvent),
+    ) {
+        if let Some(dcs_event) = self.pending_dcs_event.take() {
+            cb(dcs_event);
+        }
+        self.emit_possible_pending_event(&mut cb);
+        if self.in_soft_font {
+            font_cb(SoftFontEvent::Unterminated);
+        }
+        if self.in_image {
+            cb(if self.in_body {
+                SixelEvent::End
+            } else {
+                SixelEvent::Unterminated
+            });
+        }
+        self.clear();
+    }
+    pub fn state(&self) -> ParserState {
+        self.state
+    }
+    // in this state a sixel data byte only emits its `Data` event
+    fn is_between_instructions(&self) -> bool {
+        matches!(self.state, ParserState::Ground)
+            && self.pending_dcs_event.is_none()
+            && self.raw_instruction.is_empty()
+            && self.currently_parsing.is_empty()
+            && self.pending_event_fields.is_empty()
+    }
     fn process_byte(
         &mut self,
         byte: u8,
@@ -118,6 +280,11 @@ impl Parser {
             (ParserState::EscapeCharacter, b'P') => self.raw_instruction.try_push(byte)?,
             (ParserState::EscapeCharacter, b'\\') => self.emit_end_sequence(&mut cb)?,
             (ParserState::DeviceControlString, b'q') => self.emit_dcs_event(&mut cb)?,
+            (ParserState::DeviceControlString, b'{') => {
+                // the introducer of a soft font, its parameters are kept until the charset ends
+                self.finalize_field()?;
+                self.raw_instruction.try_push(byte)?;
+            }
             (ParserState::GraphicsRepeatIntroducer, b'?'..=b'~') => {
                 self.emit_repeat_introducer_event(byte, &mut cb)?
             }
@@ -126,8 +293,10 @@ impl Parser {
                 self.emit_single_byte_event(byte, &mut cb)?;
             }
             (_, b';') => {
-                self.raw_instruction.try_push(byte)?;
+                // a byte that fails to parse is reported by `handle_error`, so it must not be
+                // in `raw_instruction` yet
                 self.finalize_field()?;
+                self.raw_instruction.try_push(byte)?;
             }
             (_, b'0'..=b'9') => {
                 self.raw_instruction.try_push(byte)?;
@@ -144,6 +313,7 @@ impl Parser {
     fn move_to_next_state(&mut self, byte: u8) {
         self.state = match (self.state, byte) {
             (ParserState::EscapeCharacter, b'P') => ParserState::DeviceControlString,
+            (ParserState::DeviceControlString, b'{') => ParserState::SoftFontCharset,
             (ParserState::EscapeCharacter, b'\\')
             | (ParserState::DeviceControlString, b'q')
             | (ParserState::GraphicsRepeatIntroducer, b'?'..=b'~') => ParserState::Ground,
@@ -170,6 +340,7 @@ impl Parser {
         self.finalize_field()?;
         let event = SixelEvent::dcs_from_fields(&mut self.pending_event_fields)?;
         self.raw_instruction.clear();
+        self.in_image = true;
         cb(event);
         Ok(())
     }
@@ -211,19 +382,23 @@ impl Parser {
         cb(event?);
         Ok(())
     }
-    fn emit_unknown_sequences(&mut self, mut cb: impl FnMut(SixelEvent), last_byte: Option<u8>) {
+    fn emit_unknown_sequences(
+        &mut self,
+        mut cb: impl FnMut(SixelEvent),
+        mut last_byte: Option<u8>,
+    ) {
         loop {
             let mut bytes: [Option<u8>; 5] = Default::default();
             let unknown_sequence_elements = if self.raw_instruction.len() >= 5 {
                 self.raw_instruction.drain(..5).chain(None)
             } else {
-                self.raw_instruction.drain(..).chain(last_byte)
+                self.raw_instruction.drain(..).chain(last_byte.take())
             };
             for (i, byte) in unknown_sequence_elements.enumerate() {
                 bytes[i] = Some(byte);
             }
             cb(SixelEvent::UnknownSequence(bytes));
-            if self.raw_instruction.is_empty() {
+            if self.raw_instruction.is_empty() && last_byte.is_none() {
                 break;
             }
         }
@@ -274,7 +449,9 @@ impl Parser {
         }
     }
     fn finalize_field(&mut self) -> Result<(), ParserError> {
-        if !self.currently_parsing.is_empty() {
+        // an empty field inside of an instruction is an omitted parameter, which keeps the
+        // position of the ones after it
+        if !self.currently_parsing.is_empty() || !self.raw_instruction.is_empty() {
             let mut field: ArrayVec<u8, 5> = Default::default();
             for byte in self.currently_parsing.drain(..) {
                 // we don't use collect here because ArrayVec doesn't implement Try and so
@@ -286,7 +463,17 @@ impl Parser {
         Ok(())
     }
     fn clear(&mut self) {
-        drop(std::mem::replace(self, Parser::new()));
+        drop(std::mem::take(self));
+    }
+}
+
+fn is_sixel_data(byte: u8) -> bool {
+    (b'?'..=b'~').contains(&byte)
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
     }
 }
 
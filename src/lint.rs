@@ -0,0 +1,293 @@
+//! Checks a [`SixelEvent`] stream for violations of the sixel spec.
+//!
+//! [`Linter`] is given events along with the byte span that produced them (see
+//! [`SpannedParser`]) and collects a [`Diagnostic`] for every problem it finds. [`lint`] does this
+//! for a complete buffer of sixel bytes.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::lint::{lint, LintCode, Severity};
+//!
+//! let diagnostics = lint(b"\x1bPq#1;2;100;0;0#1~~\"1;1;2;6-");
+//! assert_eq!(diagnostics[0].code, LintCode::RasterAttributeAfterData);
+//! assert_eq!(diagnostics[0].span, 19..27);
+//! assert_eq!(diagnostics[1].code, LintCode::MissingEnd);
+//! assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+
+use crate::placement::ImageExtent;
+use crate::{ColorCoordinateSystem, SixelEvent, SpannedParser};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LintCode {
+    /// Raster attributes are only valid before the first sixel data in an image.
+    RasterAttributeAfterData,
+    /// The pixel aspect ratio (`pan`/`pad`) has a zero component.
+    ZeroAspectRatio,
+    /// A color register is selected without being defined earlier in the image, so the terminal's
+    /// default palette is used.
+    ColorSelectedBeforeDefinition,
+    /// Sixel data paints outside of the `ph`×`pv` area declared in the raster attributes.
+    DataOutsideRasterArea,
+    /// An image that was started is never terminated with `ST`.
+    MissingEnd,
+    /// Bytes that could not be tokenized.
+    UnknownSequence,
+    /// An HLS or RGB color component outside of its valid range.
+    ColorOutOfRange,
+}
+
+impl LintCode {
+    /// A stable identifier for this code, suitable for filtering in CI.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintCode::RasterAttributeAfterData => "raster-attribute-after-data",
+            LintCode::ZeroAspectRatio => "zero-aspect-ratio",
+            LintCode::ColorSelectedBeforeDefinition => "color-selected-before-definition",
+            LintCode::DataOutsideRasterArea => "data-outside-raster-area",
+            LintCode::MissingEnd => "missing-end",
+            LintCode::UnknownSequence => "unknown-sequence",
+            LintCode::ColorOutOfRange => "color-out-of-range",
+        }
+    }
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintCode::ColorSelectedBeforeDefinition | LintCode::DataOutsideRasterArea => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for LintCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: LintCode,
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(code: LintCode, span: Range<usize>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: code.severity(),
+            code,
+            span,
+            message: message.into(),
+        }
+    }
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}..{}: {}",
+            self.severity, self.code, self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Linter {
+    diagnostics: Vec<Diagnostic>,
+    image_start: Option<usize>,
+    end_offset: usize,
+    seen_data: bool,
+    defined_colors: BTreeSet<u16>,
+    raster_area: Option<(Option<usize>, Option<usize>)>,
+    reported_data_outside_raster_area: bool,
+    extent: ImageExtent,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Linter::default()
+    }
+    pub fn advance(&mut self, event: SixelEvent, span: Range<usize>) {
+        self.end_offset = std::cmp::max(self.end_offset, span.end);
+        match event {
+            SixelEvent::Dcs { .. } => {
+                self.check_missing_end(span.start);
+                self.start_image(span.start);
+            }
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                if self.seen_data {
+                    self.report(
+                        LintCode::RasterAttributeAfterData,
+                        span.clone(),
+                        "raster attributes after sixel data are ignored",
+                    );
+                } else {
+                    self.raster_area = Some((ph, pv));
+                }
+                if pan == 0 || pad == 0 {
+                    self.report(
+                        LintCode::ZeroAspectRatio,
+                        span,
+                        format!("invalid pixel aspect ratio {}:{}", pan, pad),
+                    );
+                }
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: Some(color_coordinate_system),
+            } => {
+                self.defined_colors.insert(color_number);
+                self.check_color_range(color_number, color_coordinate_system, span);
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: None,
+            } => {
+                if !self.defined_colors.contains(&color_number) {
+                    self.report(
+                        LintCode::ColorSelectedBeforeDefinition,
+                        span,
+                        format!(
+                            "color register {} selected before it was defined",
+                            color_number
+                        ),
+                    );
+                }
+            }
+            SixelEvent::Data { .. } | SixelEvent::Repeat { .. } => self.paint(event, span),
+            SixelEvent::GotoBeginningOfLine | SixelEvent::GotoNextLine => {
+                self.extent.advance(event)
+            }
+            SixelEvent::UnknownSequence(_) => match self.diagnostics.last_mut() {
+                // an unknown sequence is reported in chunks, these are merged into one diagnostic
+                Some(last)
+                    if last.code == LintCode::UnknownSequence && last.span.end == span.start =>
+                {
+                    last.span.end = span.end;
+                }
+                _ => self.report(LintCode::UnknownSequence, span, "unknown sequence"),
+            },
+            SixelEvent::End => self.image_start = None,
+            SixelEvent::Unterminated => self.check_missing_end(span.end),
+        }
+    }
+    /// Finishes linting, reporting images that were never terminated.
+    pub fn finish(mut self) -> Vec<Diagnostic> {
+        self.check_missing_end(self.end_offset);
+        self.diagnostics
+    }
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    fn start_image(&mut self, offset: usize) {
+        *self = Linter {
+            diagnostics: std::mem::take(&mut self.diagnostics),
+            image_start: Some(offset),
+            end_offset: self.end_offset,
+            ..Default::default()
+        };
+    }
+    fn check_missing_end(&mut self, offset: usize) {
+        if let Some(image_start) = self.image_start.take() {
+            self.report(
+                LintCode::MissingEnd,
+                image_start..offset,
+                "image is not terminated with ST",
+            );
+        }
+    }
+    fn check_color_range(
+        &mut self,
+        color_number: u16,
+        color_coordinate_system: ColorCoordinateSystem,
+        span: Range<usize>,
+    ) {
+        let in_range = match color_coordinate_system {
+            ColorCoordinateSystem::HLS(h, l, s) => h <= 360 && l <= 100 && s <= 100,
+            ColorCoordinateSystem::RGB(r, g, b) => r <= 100 && g <= 100 && b <= 100,
+        };
+        if !in_range {
+            self.report(
+                LintCode::ColorOutOfRange,
+                span,
+                format!(
+                    "color register {} is defined out of range as {:?}",
+                    color_number, color_coordinate_system
+                ),
+            );
+        }
+    }
+    fn paint(&mut self, event: SixelEvent, span: Range<usize>) {
+        self.seen_data = true;
+        let (first_column, band) = (self.extent.column(), self.extent.band());
+        self.extent.advance(event);
+        if self.reported_data_outside_raster_area {
+            return;
+        }
+        // raster attributes after sixel data are ignored, so the data painted before this event
+        // is inside of the raster area
+        if let Some((ph, pv)) = self.raster_area {
+            let (width, height) = self.extent.painted_size();
+            let outside_width = ph.is_some_and(|ph| width > ph);
+            let outside_height = pv.is_some_and(|pv| height > pv);
+            if outside_width || outside_height {
+                self.reported_data_outside_raster_area = true;
+                self.report(
+                    LintCode::DataOutsideRasterArea,
+                    span,
+                    format!(
+                        "sixel data at column {}, band {} is outside of the declared {}x{} area",
+                        first_column,
+                        band,
+                        ph.map_or("?".to_owned(), |ph| ph.to_string()),
+                        pv.map_or("?".to_owned(), |pv| pv.to_string()),
+                    ),
+                );
+            }
+        }
+    }
+    fn report(&mut self, code: LintCode, span: Range<usize>, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::new(code, span, message));
+    }
+}
+
+/// Lints a complete buffer of sixel bytes.
+pub fn lint(bytes: &[u8]) -> Vec<Diagnostic> {
+    let mut parser = SpannedParser::new();
+    let mut linter = Linter::new();
+    for byte in bytes {
+        parser.advance(byte, |event, span| linter.advance(event, span));
+    }
+    parser.finish(|event, span| linter.advance(event, span));
+    linter.finish()
+}
+
+#[cfg(test)]
+#[path = "./lint_tests.rs"]
+mod tests;
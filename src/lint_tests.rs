@@ -0,0 +1,155 @@
+use crate::lint::{lint, Diagnostic, LintCode, Linter, Severity};
+use crate::SixelEvent;
+
+fn codes(diagnostics: &[Diagnostic]) -> Vec<LintCode> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect()
+}
+
+#[test]
+fn valid_image_has_no_diagnostics() {
+    let sample = "
+        \u{1b}Pq
+        \"2;1;100;200
+        #0;2;0;0;0#1;2;100;100;0#2;2;0;100;0
+        #1~~@@vv@@~~@@~~$
+        #2??}}GG}}??}}??-
+        #1!14@
+        \u{1b}\\
+    ";
+    assert_eq!(lint(sample.as_bytes()), vec![]);
+}
+
+#[test]
+fn raster_attribute_after_data() {
+    let diagnostics = lint(b"\x1bPq#1;2;0;0;0~\"1;1;10;10$\x1b\\");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::RasterAttributeAfterData]
+    );
+    assert_eq!(diagnostics[0].span, 14..24);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn zero_aspect_ratio() {
+    let diagnostics = lint(b"\x1bPq\"0;1;10;10\x1b\\");
+    assert_eq!(codes(&diagnostics), vec![LintCode::ZeroAspectRatio]);
+    assert_eq!(diagnostics[0].span, 3..13);
+}
+
+#[test]
+fn color_selected_before_definition() {
+    let diagnostics = lint(b"\x1bPq#3~#3;2;0;0;0#3~\x1b\\");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::ColorSelectedBeforeDefinition]
+    );
+    assert_eq!(diagnostics[0].span, 3..5);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+}
+
+#[test]
+fn color_definitions_do_not_carry_over_to_the_next_image() {
+    let diagnostics = lint(b"\x1bPq#3;2;0;0;0#3~\x1b\\\x1bPq#3~\x1b\\");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::ColorSelectedBeforeDefinition]
+    );
+    assert_eq!(diagnostics[0].span, 21..23);
+}
+
+#[test]
+fn data_outside_raster_area_is_reported_once() {
+    let diagnostics = lint(b"\x1bPq\"1;1;2;3#1;2;0;0;0??~~~-~~\x1b\\");
+    assert_eq!(codes(&diagnostics), vec![LintCode::DataOutsideRasterArea]);
+    assert_eq!(diagnostics[0].span, 23..24);
+}
+
+#[test]
+fn blank_data_outside_raster_area_is_not_reported() {
+    let diagnostics = lint(b"\x1bPq\"1;1;2;6#1;2;0;0;0~~!10?\x1b\\");
+    assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn zero_repeat_count_at_the_start_of_a_band() {
+    let diagnostics = lint(b"\x1bPq\"1;1;4;6#1;2;100;0;0#1!0~~-\x1b\\");
+    assert_eq!(diagnostics, vec![]);
+    let diagnostics = lint(b"\x1bPq\"1;1;4;12#1;2;100;0;0#1~~-#1!0~\x1b\\");
+    assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn zero_repeat_count_does_not_grow_the_painted_area() {
+    let diagnostics = lint(b"\x1bPq\"1;1;4;6#1;2;100;0;0#1~~-#1!0~\x1b\\");
+    assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn missing_end() {
+    let diagnostics = lint(b"\x1bPq#1;2;0;0;0~~\x1bPq~~\x1b\\\x1bPq~");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::MissingEnd, LintCode::MissingEnd]
+    );
+    assert_eq!(diagnostics[0].span, 0..15);
+    assert_eq!(diagnostics[1].span, 22..26);
+}
+
+#[test]
+fn unknown_sequence_chunks_are_merged() {
+    let diagnostics = lint(b"\x1bPq#0;1;100;1;2;3;4~\x1b\\");
+    assert_eq!(codes(&diagnostics), vec![LintCode::UnknownSequence]);
+    assert_eq!(diagnostics[0].span, 3..19);
+}
+
+#[test]
+fn colors_out_of_range() {
+    let diagnostics = lint(b"\x1bPq#1;2;101;0;0#2;1;360;100;100#3;1;361;0;0\x1b\\");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::ColorOutOfRange, LintCode::ColorOutOfRange]
+    );
+    assert_eq!(diagnostics[0].span, 3..15);
+    assert_eq!(diagnostics[1].span, 31..43);
+}
+
+#[test]
+fn linter_can_be_driven_by_events() {
+    let mut linter = Linter::new();
+    linter.advance(SixelEvent::new_dcs(None, None, None), 0..3);
+    linter.advance(
+        SixelEvent::UnknownSequence([Some(b'%'), None, None, None, None]),
+        3..4,
+    );
+    assert_eq!(codes(linter.diagnostics()), vec![LintCode::UnknownSequence]);
+    let diagnostics = linter.finish();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::UnknownSequence, LintCode::MissingEnd]
+    );
+    assert_eq!(diagnostics[1].span, 0..4);
+}
+
+#[test]
+fn diagnostic_display() {
+    let diagnostics = lint(b"\x1bPq\"0;1\x1b\\");
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error[zero-aspect-ratio] 3..7: invalid pixel aspect ratio 0:1"
+    );
+}
+
+#[test]
+fn instruction_at_the_end_of_input_is_linted() {
+    let diagnostics = lint(b"\x1bPq#1;2;300;0;0");
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::ColorOutOfRange, LintCode::MissingEnd]
+    );
+    assert_eq!(diagnostics[0].span, 3..15);
+    assert_eq!(diagnostics[1].span, 0..15);
+}
@@ -0,0 +1,316 @@
+//! An event-level optimizer that rewrites a [`SixelEvent`] stream into an equivalent, smaller one.
+//!
+//! Each image (the events between a `Dcs` and its `End`) is buffered and re-emitted with:
+//! - color selections only where the current color actually changes
+//! - runs of identical data bytes collapsed into `Repeat` events where this is shorter
+//! - color passes that paint nothing removed, along with redundant `$` events
+//! - definitions of color registers that are never used for painting removed
+//!
+//! The optimizer assumes every image has its own private color registers (which is the default
+//! on most terminals). If registers are shared between images (see
+//! [`RegisterMode`](crate::RegisterMode)), a definition that is unused in one image might be used
+//! by a later one and should not be dropped.
+
+use std::collections::BTreeSet;
+
+use crate::SixelEvent;
+
+const BLANK: u8 = b'?';
+
+#[derive(Clone, Debug, Default)]
+pub struct Optimizer {
+    image: Vec<SixelEvent>,
+    in_image: bool,
+    bytes_in: usize,
+    bytes_out: usize,
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Optimizer::default()
+    }
+    /// Feeds a single event to the optimizer. Events inside an image are buffered until its `End`
+    /// event arrives, at which point the optimized image is passed to the callback.
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(SixelEvent)) {
+        self.bytes_in += event.serialized_len();
+        match event {
+            SixelEvent::Dcs { .. } => {
+                if self.in_image {
+                    self.flush_image(&mut cb);
+                }
+                self.in_image = true;
+                self.image.push(event);
+            }
+            SixelEvent::End | SixelEvent::Unterminated if self.in_image => {
+                self.image.push(event);
+                self.flush_image(&mut cb);
+                self.in_image = false;
+            }
+            _ if self.in_image => self.image.push(event),
+            _ => self.emit(event, &mut cb),
+        }
+    }
+    /// Flushes an image that was not terminated by an `End` event.
+    pub fn finish(&mut self, mut cb: impl FnMut(SixelEvent)) {
+        if self.in_image {
+            self.flush_image(&mut cb);
+            self.in_image = false;
+        }
+    }
+    /// The serialized size of all events given to the optimizer so far.
+    pub fn bytes_in(&self) -> usize {
+        self.bytes_in
+    }
+    /// The serialized size of all events emitted by the optimizer so far.
+    pub fn bytes_out(&self) -> usize {
+        self.bytes_out
+    }
+    pub fn bytes_saved(&self) -> usize {
+        self.bytes_in.saturating_sub(self.bytes_out)
+    }
+    fn emit(&mut self, event: SixelEvent, cb: &mut impl FnMut(SixelEvent)) {
+        self.bytes_out += event.serialized_len();
+        cb(event);
+    }
+    fn flush_image(&mut self, cb: &mut impl FnMut(SixelEvent)) {
+        let image = std::mem::take(&mut self.image);
+        let analysis = ImageAnalysis::new(&image);
+        let mut writer = ImageWriter::new(&analysis);
+        for event in image {
+            writer.advance(event, |event| self.emit(event, cb));
+        }
+        writer.finish_pass(|event| self.emit(event, cb));
+    }
+}
+
+#[derive(Debug, Default)]
+struct ImageAnalysis {
+    painting_registers: BTreeSet<u16>,
+    keep_register_zero: bool,
+    required_width: usize,
+}
+
+impl ImageAnalysis {
+    pub fn new(image: &[SixelEvent]) -> Self {
+        let mut analysis = ImageAnalysis::default();
+        let mut current_color = None;
+        let mut column = 0;
+        let mut width = 0;
+        let mut declared_width = 0;
+        for event in image {
+            match *event {
+                SixelEvent::Dcs {
+                    transparent_background,
+                    ..
+                } => {
+                    // the background is filled with register 0 unless it's transparent
+                    analysis.keep_register_zero = transparent_background != Some(1);
+                }
+                SixelEvent::RasterAttribute { ph, .. } => {
+                    declared_width = ph.unwrap_or(0);
+                }
+                SixelEvent::ColorIntroducer { color_number, .. } => {
+                    current_color = Some(color_number);
+                }
+                SixelEvent::Data { byte } => {
+                    analysis.mark_painting(current_color, byte);
+                    column += 1;
+                }
+                SixelEvent::Repeat {
+                    repeat_count,
+                    byte_to_repeat,
+                } => {
+                    analysis.mark_painting(current_color, byte_to_repeat);
+                    column += repeat_count;
+                }
+                SixelEvent::GotoBeginningOfLine | SixelEvent::GotoNextLine => {
+                    column = 0;
+                }
+                SixelEvent::UnknownSequence(_) | SixelEvent::End | SixelEvent::Unterminated => {}
+            }
+            width = std::cmp::max(width, column);
+        }
+        if width > declared_width {
+            // dropping trailing blanks must not shrink the image
+            analysis.required_width = width;
+        }
+        analysis
+    }
+    fn mark_painting(&mut self, current_color: Option<u16>, byte: u8) {
+        if byte != BLANK {
+            match current_color {
+                Some(color) => {
+                    self.painting_registers.insert(color);
+                }
+                // painting with the terminal's default color
+                None => self.keep_register_zero = true,
+            }
+        }
+    }
+    fn keeps_definition(&self, color_number: u16) -> bool {
+        self.painting_registers.contains(&color_number)
+            || (color_number == 0 && self.keep_register_zero)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    color: Option<u16>,
+    byte: u8,
+    count: usize,
+}
+
+#[derive(Debug)]
+struct ImageWriter<'a> {
+    analysis: &'a ImageAnalysis,
+    wanted_color: Option<u16>,
+    emitted_color: Option<u16>,
+    runs: Vec<Run>,
+    column: usize,
+    emitted_column: usize,
+    emitted_width: usize,
+    pass_started: bool,
+    pending_beginning_of_line: bool,
+}
+
+impl<'a> ImageWriter<'a> {
+    pub fn new(analysis: &'a ImageAnalysis) -> Self {
+        ImageWriter {
+            analysis,
+            wanted_color: None,
+            emitted_color: None,
+            runs: vec![],
+            column: 0,
+            emitted_column: 0,
+            emitted_width: 0,
+            pass_started: false,
+            pending_beginning_of_line: false,
+        }
+    }
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(SixelEvent)) {
+        match event {
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: Some(_),
+            } => {
+                self.wanted_color = Some(color_number);
+                if self.analysis.keeps_definition(color_number) {
+                    self.flush_runs(&mut cb);
+                    self.emitted_color = Some(color_number);
+                    cb(event);
+                }
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system: None,
+            } => {
+                self.wanted_color = Some(color_number);
+            }
+            SixelEvent::Data { byte } => self.push_run(byte, 1),
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => self.push_run(byte_to_repeat, repeat_count),
+            SixelEvent::GotoBeginningOfLine => {
+                self.finish_pass(&mut cb);
+            }
+            SixelEvent::GotoNextLine => {
+                self.finish_pass(&mut cb);
+                self.pending_beginning_of_line = false;
+                cb(event);
+            }
+            SixelEvent::End | SixelEvent::Unterminated => {
+                self.finish_pass(&mut cb);
+                self.pending_beginning_of_line = false;
+                cb(event);
+            }
+            SixelEvent::Dcs { .. }
+            | SixelEvent::RasterAttribute { .. }
+            | SixelEvent::UnknownSequence(_) => {
+                self.flush_runs(&mut cb);
+                cb(event);
+            }
+        }
+    }
+    pub fn finish_pass(&mut self, mut cb: impl FnMut(SixelEvent)) {
+        let trailing_blanks: usize = self
+            .runs
+            .iter()
+            .rev()
+            .take_while(|run| run.byte == BLANK)
+            .map(|run| run.count)
+            .sum();
+        let needed_for_width = self.emitted_width < self.analysis.required_width
+            && self.column - trailing_blanks < self.analysis.required_width
+            && self.column >= self.analysis.required_width;
+        if !needed_for_width {
+            while self.runs.last().is_some_and(|run| run.byte == BLANK) {
+                self.runs.pop();
+            }
+        }
+        self.flush_runs(&mut cb);
+        if self.pass_started {
+            self.pending_beginning_of_line = true;
+        }
+        self.pass_started = false;
+        self.column = 0;
+        self.emitted_column = 0;
+    }
+    fn push_run(&mut self, byte: u8, count: usize) {
+        let color = if byte == BLANK {
+            None
+        } else {
+            self.wanted_color
+        };
+        self.column += count;
+        match self.runs.last_mut() {
+            // a repeat count of 0 is kept as written: some terminals paint one column for it, so
+            // it's not merged with the data around it
+            Some(run) if run.byte == byte && run.color == color && run.count > 0 && count > 0 => {
+                run.count += count
+            }
+            _ => self.runs.push(Run { color, byte, count }),
+        }
+    }
+    fn flush_runs(&mut self, mut cb: impl FnMut(SixelEvent)) {
+        if self.runs.is_empty() {
+            return;
+        }
+        if !self.pass_started && self.pending_beginning_of_line {
+            cb(SixelEvent::GotoBeginningOfLine);
+            self.pending_beginning_of_line = false;
+        }
+        self.pass_started = true;
+        for run in self.runs.drain(..) {
+            if run.byte != BLANK && run.color.is_some() && run.color != self.emitted_color {
+                cb(SixelEvent::ColorIntroducer {
+                    color_number: run.color.unwrap_or(0),
+                    color_coordinate_system: None,
+                });
+                self.emitted_color = run.color;
+            }
+            emit_run(run.byte, run.count, &mut cb);
+            self.emitted_column += run.count;
+        }
+        self.emitted_width = std::cmp::max(self.emitted_width, self.emitted_column);
+    }
+}
+
+fn emit_run(byte: u8, count: usize, mut cb: impl FnMut(SixelEvent)) {
+    // "!<count><byte>" only pays off once it is shorter than the bytes it replaces
+    let repeat_len = 2 + count.to_string().len();
+    if repeat_len < count || count == 0 {
+        cb(SixelEvent::Repeat {
+            repeat_count: count,
+            byte_to_repeat: byte,
+        });
+    } else {
+        for _ in 0..count {
+            cb(SixelEvent::Data { byte });
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./optimizer_tests.rs"]
+mod tests;
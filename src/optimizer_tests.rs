@@ -0,0 +1,148 @@
+use std::str;
+
+use crate::{Optimizer, Parser, SixelEvent};
+
+fn parse(sample: &str) -> Vec<SixelEvent> {
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(byte, |sixel_event| events.push(sixel_event));
+    }
+    events
+}
+
+fn optimize(sample: &str) -> (String, Optimizer) {
+    let mut optimizer = Optimizer::new();
+    let mut serialized = vec![];
+    for event in parse(sample) {
+        optimizer.advance(event, |event| event.write_to(&mut serialized).unwrap());
+    }
+    optimizer.finish(|event| event.write_to(&mut serialized).unwrap());
+    (String::from_utf8(serialized).unwrap(), optimizer)
+}
+
+#[test]
+fn serialized_events_parse_back_to_the_same_events() {
+    let sample = "
+        \u{1b}P0;1;5q
+        \"2;1;100;200
+        #0;2;0;0;0#1;2;100;100;0#2;1;120;50;100
+        #1~~@@vv@@~~@@~~$
+        #2??}}GG}}??}}??-
+        #1!14@
+        \u{1b}\\
+    ";
+    let events = parse(sample);
+    let mut serialized = vec![];
+    for event in &events {
+        event.write_to(&mut serialized).unwrap();
+    }
+    let serialized_len: usize = events.iter().map(|event| event.serialized_len()).sum();
+    assert_eq!(serialized.len(), serialized_len);
+    assert_eq!(parse(str::from_utf8(&serialized).unwrap()), events);
+}
+
+#[test]
+fn redundant_color_selections_are_removed() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~#1@#1~#2~#2~\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1~@~#2~~\u{1b}\\");
+}
+
+#[test]
+fn long_data_runs_become_repeats() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~~~~~~~~~~~~@@@!2@!3@~~~\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1!12~!8@~~~\u{1b}\\");
+}
+
+#[test]
+fn short_repeats_become_data() {
+    let (optimized, _) = optimize("\u{1b}Pq#1!2~!3@\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1~~@@@\u{1b}\\");
+}
+
+#[test]
+fn zero_repeats_are_kept_as_written() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~!0~~#2!0@\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1~!0~~#2!0@\u{1b}\\");
+}
+
+#[test]
+fn empty_color_passes_are_removed() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~~$#2??$#3!2?$#4@@\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1~~$#4@@\u{1b}\\");
+}
+
+#[test]
+fn trailing_blanks_and_beginning_of_line_before_next_line_are_removed() {
+    let (optimized, _) = optimize("\u{1b}Pq\"1;1;4;12#1~~??$-#1~~$\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq\"1;1;4;12#1~~-~~\u{1b}\\");
+}
+
+#[test]
+fn trailing_blanks_that_define_the_image_width_are_kept() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~~!10?$#2@@???\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#1~~!10?$#2@@\u{1b}\\");
+}
+
+#[test]
+fn unused_color_definitions_are_removed() {
+    let (optimized, _) =
+        optimize("\u{1b}P0;1q\"1;1;6;6#1;2;100;0;0#2;2;0;100;0#3;2;0;0;100#2~~#3????\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}P0;1q\"1;1;6;6#2;2;0;100;0~~\u{1b}\\");
+}
+
+#[test]
+fn constructed_events_parse_back_to_the_same_events() {
+    let events = [
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::new_dcs(Some(7), Some(1), Some(3)),
+        SixelEvent::new_dcs(None, Some(1), None),
+        SixelEvent::new_dcs(None, None, Some(2)),
+        SixelEvent::new_raster(2, 1, None, None).unwrap(),
+        SixelEvent::new_raster(2, 1, Some(10), None).unwrap(),
+        SixelEvent::new_raster(2, 1, None, Some(20)).unwrap(),
+        SixelEvent::new_color_introducer(3, Some(2), Some(0), Some(50), Some(100)).unwrap(),
+        SixelEvent::new_color_introducer(3, None, None, None, None).unwrap(),
+        SixelEvent::new_repeat(12, b'~').unwrap(),
+        SixelEvent::Data { byte: b'@' },
+        SixelEvent::GotoBeginningOfLine,
+        SixelEvent::GotoNextLine,
+        SixelEvent::End,
+    ];
+    let mut serialized = vec![];
+    for event in &events {
+        event.write_to(&mut serialized).unwrap();
+    }
+    assert_eq!(parse(str::from_utf8(&serialized).unwrap()), events);
+}
+
+#[test]
+fn background_color_definition_is_kept_for_opaque_backgrounds() {
+    let (optimized, _) = optimize("\u{1b}Pq#0;2;0;0;0#1;2;100;0;0#2;2;0;100;0#2~~\u{1b}\\");
+    assert_eq!(optimized, "\u{1b}Pq#0;2;0;0;0#2;2;0;100;0~~\u{1b}\\");
+}
+
+#[test]
+fn events_outside_of_images_are_passed_through() {
+    let mut optimizer = Optimizer::new();
+    let mut events = vec![];
+    optimizer.advance(SixelEvent::GotoNextLine, |event| events.push(event));
+    optimizer.advance(SixelEvent::End, |event| events.push(event));
+    assert_eq!(events, vec![SixelEvent::GotoNextLine, SixelEvent::End]);
+}
+
+#[test]
+fn unterminated_image_is_flushed_on_finish() {
+    let (optimized, _) = optimize("\u{1b}Pq#1~#1~#1~");
+    assert_eq!(optimized, "\u{1b}Pq#1~~~");
+}
+
+#[test]
+fn byte_savings_are_reported() {
+    let sample = "\u{1b}Pq#1;2;100;0;0#1~~~~~~~~~~$#1~~~~~~~~~~\u{1b}\\";
+    let (optimized, optimizer) = optimize(sample);
+    assert_eq!(optimized, "\u{1b}Pq#1;2;100;0;0!10~$!10~\u{1b}\\");
+    assert_eq!(optimizer.bytes_in(), sample.len());
+    assert_eq!(optimizer.bytes_out(), optimized.len());
+    assert_eq!(optimizer.bytes_saved(), sample.len() - optimized.len());
+}
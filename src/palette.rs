@@ -0,0 +1,121 @@
+//! Color registers and the conversion of sixel colors to RGB.
+
+use crate::ColorCoordinateSystem;
+
+/// The colors of the VT340's first 16 registers, in percent.
+const VT340_COLORS: [(usize, usize, usize); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Whether each sixel image gets its own color registers or all images share one set of them, as
+/// selected by xterm's private color registers mode (DECSET 1070).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegisterMode {
+    /// Every image starts out with the default palette. This is the default on most terminals.
+    #[default]
+    Private,
+    /// Colors defined by an image stay in the registers for the images after it.
+    Shared,
+}
+
+/// A set of color registers, each holding an opaque RGBA color.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// A palette with the given number of registers (at least one), starting with the VT340's
+    /// default colors. Registers past the 16th are black.
+    pub fn vt340(registers: usize) -> Self {
+        let mut colors = vec![[0, 0, 0, 255]; std::cmp::max(registers, 1)];
+        for (register, color) in colors.iter_mut().take(VT340_COLORS.len()).enumerate() {
+            let [r, g, b] = vt340_color(register as u16).to_rgb();
+            *color = [r, g, b, 255];
+        }
+        Palette { colors }
+    }
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+    /// The color of a register. Register numbers past the end of the palette wrap around.
+    pub fn get(&self, register: u16) -> [u8; 4] {
+        self.colors[register as usize % self.colors.len()]
+    }
+    pub fn set(&mut self, register: u16, color: ColorCoordinateSystem) {
+        let [r, g, b] = color.to_rgb();
+        let len = self.colors.len();
+        self.colors[register as usize % len] = [r, g, b, 255];
+    }
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.colors
+    }
+}
+
+/// The VT340's default color of a register.
+pub(crate) fn vt340_color(register: u16) -> ColorCoordinateSystem {
+    let (r, g, b) = VT340_COLORS
+        .get(register as usize)
+        .copied()
+        .unwrap_or((0, 0, 0));
+    ColorCoordinateSystem::RGB(r, g, b)
+}
+
+impl ColorCoordinateSystem {
+    /// Converts this color to 8-bit RGB. Components out of their valid range are clamped (or,
+    /// for hues, wrapped around).
+    ///
+    /// Note that DEC's HLS puts blue at a hue of 0°, red at 120° and green at 240°.
+    pub fn to_rgb(&self) -> [u8; 3] {
+        match *self {
+            ColorCoordinateSystem::RGB(r, g, b) => [percent(r), percent(g), percent(b)],
+            ColorCoordinateSystem::HLS(h, l, s) => hls_to_rgb(h, l, s),
+        }
+    }
+}
+
+fn percent(value: usize) -> u8 {
+    ((std::cmp::min(value, 100) * 255 + 50) / 100) as u8
+}
+
+fn hls_to_rgb(h: usize, l: usize, s: usize) -> [u8; 3] {
+    // rotate DEC hues so that red is at 0°, as in the usual HSL formulas
+    let h = ((h + 240) % 360) as f64 / 60.0;
+    let l = std::cmp::min(l, 100) as f64 / 100.0;
+    let s = std::cmp::min(s, 100) as f64 / 100.0;
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as usize {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+#[cfg(test)]
+#[path = "./palette_tests.rs"]
+mod tests;
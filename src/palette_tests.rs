@@ -0,0 +1,69 @@
+use super::*;
+
+#[test]
+fn rgb_percentages() {
+    assert_eq!(
+        ColorCoordinateSystem::RGB(0, 50, 100).to_rgb(),
+        [0, 128, 255]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::RGB(20, 80, 150).to_rgb(),
+        [51, 204, 255]
+    );
+}
+
+#[test]
+fn dec_hues() {
+    assert_eq!(ColorCoordinateSystem::HLS(0, 50, 100).to_rgb(), [0, 0, 255]);
+    assert_eq!(
+        ColorCoordinateSystem::HLS(120, 50, 100).to_rgb(),
+        [255, 0, 0]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::HLS(240, 50, 100).to_rgb(),
+        [0, 255, 0]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::HLS(180, 50, 100).to_rgb(),
+        [255, 255, 0]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::HLS(480, 50, 100).to_rgb(),
+        [255, 0, 0]
+    );
+}
+
+#[test]
+fn hls_lightness_and_saturation() {
+    assert_eq!(ColorCoordinateSystem::HLS(0, 0, 100).to_rgb(), [0, 0, 0]);
+    assert_eq!(
+        ColorCoordinateSystem::HLS(0, 100, 100).to_rgb(),
+        [255, 255, 255]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::HLS(77, 50, 0).to_rgb(),
+        [128, 128, 128]
+    );
+    assert_eq!(
+        ColorCoordinateSystem::HLS(120, 25, 100).to_rgb(),
+        [128, 0, 0]
+    );
+}
+
+#[test]
+fn vt340_palette() {
+    let palette = Palette::vt340(256);
+    assert_eq!(palette.len(), 256);
+    assert_eq!(palette.get(0), [0, 0, 0, 255]);
+    assert_eq!(palette.get(1), [51, 51, 204, 255]);
+    assert_eq!(palette.get(15), [204, 204, 204, 255]);
+    assert_eq!(palette.get(16), [0, 0, 0, 255]);
+}
+
+#[test]
+fn registers_wrap_around() {
+    let mut palette = Palette::vt340(16);
+    palette.set(17, ColorCoordinateSystem::RGB(100, 0, 0));
+    assert_eq!(palette.get(1), [255, 0, 0, 255]);
+    assert_eq!(palette.get(33), [255, 0, 0, 255]);
+}
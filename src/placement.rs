@@ -0,0 +1,244 @@
+//! Where terminals draw sixel images and where they leave the cursor.
+//!
+//! With sixel scrolling (DECSDM reset, xterm's `sixelScrolling` resource), an image is drawn with
+//! its top left corner at the cursor, the screen scrolls up if the image (or the cursor after it)
+//! would run past the bottom line, and the cursor moves past the image. Where exactly it ends up
+//! differs between terminals, see [`CursorAfterImage`].
+//!
+//! In sixel display mode (DECSDM set), an image is drawn at the top left corner of the screen and
+//! clipped to it, the screen never scrolls and the cursor does not move.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::placement::{Cell, CellRect, CursorAfterImage, ImageExtent, Screen};
+//! use sixel_tokenizer::Parser;
+//!
+//! let mut extent = ImageExtent::default();
+//! let mut parser = Parser::new();
+//! for byte in b"\x1bPq\"1;1;30;36#1~\x1b\\" {
+//!     parser.advance(byte, |event| extent.advance(event));
+//! }
+//! // 30x36 pixels cover 3x2 cells of 10x20 pixels
+//! let screen = Screen::new(80, 24, 10, 20);
+//! let placement = screen.place(extent, Cell { column: 4, row: 22 });
+//! assert_eq!(placement.scroll, 1);
+//! assert_eq!(placement.covered, CellRect { column: 4, row: 21, columns: 3, rows: 2 });
+//! assert_eq!(placement.cursor, Cell { column: 4, row: 23 });
+//! ```
+
+use crate::SixelEvent;
+
+/// The size in pixels of the area an image paints: the larger of its declared size (in its
+/// raster attributes) and the sixel data it actually paints. Blank sixels move the sixel cursor
+/// without growing the extent.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImageExtent {
+    pub width: usize,
+    pub height: usize,
+    painted_width: usize,
+    painted_height: usize,
+    column: usize,
+    band: usize,
+}
+
+impl ImageExtent {
+    pub fn new(width: usize, height: usize) -> Self {
+        ImageExtent {
+            width,
+            height,
+            ..ImageExtent::default()
+        }
+    }
+    /// Grows the extent to include an event of the image. A `Dcs` event starts a new image.
+    pub fn advance(&mut self, event: SixelEvent) {
+        match event {
+            SixelEvent::Dcs { .. } => *self = ImageExtent::default(),
+            SixelEvent::RasterAttribute { ph, pv, .. } => {
+                self.width = std::cmp::max(self.width, ph.unwrap_or(0));
+                self.height = std::cmp::max(self.height, pv.unwrap_or(0));
+            }
+            SixelEvent::Data { byte } => self.paint(byte, 1),
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => self.paint(byte_to_repeat, repeat_count),
+            SixelEvent::GotoBeginningOfLine => self.column = 0,
+            SixelEvent::GotoNextLine => {
+                self.column = 0;
+                self.band += 1;
+            }
+            _ => {}
+        }
+    }
+    /// The extent of the first image in a list of events.
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a SixelEvent>) -> Self {
+        let mut extent = ImageExtent::default();
+        for event in events {
+            if let SixelEvent::End | SixelEvent::Unterminated = event {
+                break;
+            }
+            extent.advance(*event);
+        }
+        extent
+    }
+    /// The size of the area the sixel data paints, without the declared size.
+    pub fn painted_size(&self) -> (usize, usize) {
+        (self.painted_width, self.painted_height)
+    }
+    /// The column the next sixel data goes to.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+    /// The band (of six rows of pixels) the next sixel data goes to.
+    pub fn band(&self) -> usize {
+        self.band
+    }
+    /// The number of columns and rows of cells the image covers.
+    pub fn cells(&self, cell_width: usize, cell_height: usize) -> (usize, usize) {
+        (
+            self.width.div_ceil(std::cmp::max(cell_width, 1)),
+            self.height.div_ceil(std::cmp::max(cell_height, 1)),
+        )
+    }
+    fn paint(&mut self, byte: u8, count: usize) {
+        // a repeat count of 0 paints nothing
+        if count == 0 {
+            return;
+        }
+        self.column = self.column.saturating_add(count);
+        let bits = byte.wrapping_sub(b'?') & 0b111111;
+        if bits != 0 {
+            let rows = 8 - bits.leading_zeros() as usize;
+            self.painted_width = std::cmp::max(self.painted_width, self.column);
+            self.painted_height = std::cmp::max(
+                self.painted_height,
+                self.band.saturating_mul(6).saturating_add(rows),
+            );
+            self.width = std::cmp::max(self.width, self.painted_width);
+            self.height = std::cmp::max(self.height, self.painted_height);
+        }
+    }
+}
+
+/// Where the cursor goes after an image in sixel scrolling mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CursorAfterImage {
+    /// The line below the image, in the column the image starts at (xterm, the VT340).
+    #[default]
+    NextLine,
+    /// The last line the image covers, in the column the image starts at.
+    LastLine,
+    /// The last line the image covers, in the column after the image (xterm's sixel scrolls
+    /// right mode, DECSET 8452).
+    RightOfImage,
+}
+
+/// A character cell, counting from 0 at the top left of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cell {
+    pub column: usize,
+    pub row: usize,
+}
+
+/// A rectangle of character cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellRect {
+    pub column: usize,
+    pub row: usize,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl CellRect {
+    pub fn contains(&self, cell: Cell) -> bool {
+        (self.column..self.column + self.columns).contains(&cell.column)
+            && (self.row..self.row + self.rows).contains(&cell.row)
+    }
+}
+
+/// The result of drawing an image on a [`Screen`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    /// The cursor after the image.
+    pub cursor: Cell,
+    /// The number of lines the screen scrolls up to make room for the image.
+    pub scroll: usize,
+    /// The visible cells the image covers, after scrolling.
+    pub covered: CellRect,
+}
+
+/// The geometry of a terminal screen and the modes that decide where images go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Screen {
+    pub columns: usize,
+    pub rows: usize,
+    /// The width of a cell in sixel pixels.
+    pub cell_width: usize,
+    /// The height of a cell in sixel pixels.
+    pub cell_height: usize,
+    /// Whether sixel scrolling is enabled, that is whether DECSDM is reset.
+    pub sixel_scrolling: bool,
+    pub cursor_after_image: CursorAfterImage,
+}
+
+impl Screen {
+    /// A screen with sixel scrolling enabled, which is the default on most terminals.
+    pub fn new(columns: usize, rows: usize, cell_width: usize, cell_height: usize) -> Self {
+        Screen {
+            columns,
+            rows,
+            cell_width,
+            cell_height,
+            sixel_scrolling: true,
+            cursor_after_image: CursorAfterImage::default(),
+        }
+    }
+    /// Places an image drawn with the cursor at the given cell.
+    pub fn place(&self, extent: ImageExtent, cursor: Cell) -> Placement {
+        let (columns, rows) = extent.cells(self.cell_width, self.cell_height);
+        let last_row = self.rows.saturating_sub(1);
+        if !self.sixel_scrolling {
+            return Placement {
+                cursor,
+                scroll: 0,
+                covered: CellRect {
+                    column: 0,
+                    row: 0,
+                    columns: std::cmp::min(columns, self.columns),
+                    rows: std::cmp::min(rows, self.rows),
+                },
+            };
+        }
+        let image_last_row = cursor.row + rows.saturating_sub(1);
+        let (cursor_row, cursor_column) = match self.cursor_after_image {
+            CursorAfterImage::NextLine if rows > 0 => (cursor.row + rows, cursor.column),
+            CursorAfterImage::NextLine | CursorAfterImage::LastLine => {
+                (image_last_row, cursor.column)
+            }
+            CursorAfterImage::RightOfImage => (
+                image_last_row,
+                std::cmp::min(cursor.column + columns, self.columns.saturating_sub(1)),
+            ),
+        };
+        let scroll = std::cmp::max(cursor_row, image_last_row).saturating_sub(last_row);
+        let top = cursor.row as isize - scroll as isize;
+        let visible_rows = (rows as isize + std::cmp::min(top, 0)).max(0) as usize;
+        Placement {
+            cursor: Cell {
+                column: cursor_column,
+                row: cursor_row - scroll,
+            },
+            scroll,
+            covered: CellRect {
+                column: cursor.column,
+                row: top.max(0) as usize,
+                columns: std::cmp::min(columns, self.columns.saturating_sub(cursor.column)),
+                rows: std::cmp::min(visible_rows, self.rows),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./placement_tests.rs"]
+mod tests;
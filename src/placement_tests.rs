@@ -0,0 +1,176 @@
+use super::*;
+use crate::Parser;
+
+fn extent_of(bytes: &[u8]) -> ImageExtent {
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in bytes {
+        parser.advance(byte, |event| events.push(event));
+    }
+    ImageExtent::from_events(&events)
+}
+
+fn size(extent: ImageExtent) -> (usize, usize) {
+    (extent.width, extent.height)
+}
+
+fn cell(column: usize, row: usize) -> Cell {
+    Cell { column, row }
+}
+
+#[test]
+fn extent_of_painted_data() {
+    // the highest set bit of the last band decides the height
+    assert_eq!(size(extent_of(b"\x1bPq~~-!3@\x1b\\")), (3, 7));
+    // blank columns and bands only count once something is painted after them
+    assert_eq!(size(extent_of(b"\x1bPq~??-\x1b\\")), (1, 6));
+    assert_eq!(size(extent_of(b"\x1bPq~$??~\x1b\\")), (3, 6));
+}
+
+#[test]
+fn extent_is_at_least_the_declared_size() {
+    assert_eq!(size(extent_of(b"\x1bPq\"1;1;20;30~\x1b\\")), (20, 30));
+    assert_eq!(size(extent_of(b"\x1bPq\"1;1;2;2!5~\x1b\\")), (5, 6));
+}
+
+#[test]
+fn painted_size_and_sixel_cursor() {
+    let extent = extent_of(b"\x1bPq\"1;1;20;30~-@!4?\x1b\\");
+    assert_eq!(size(extent), (20, 30));
+    assert_eq!(extent.painted_size(), (1, 7));
+    assert_eq!((extent.column(), extent.band()), (5, 1));
+}
+
+#[test]
+fn extent_of_the_first_image() {
+    let extent = extent_of(b"\x1bPq~\x1b\\\x1bPq!10~-~\x1b\\");
+    assert_eq!(size(extent), (1, 6));
+}
+
+#[test]
+fn dcs_starts_a_new_extent() {
+    let mut extent = ImageExtent::new(10, 10);
+    extent.advance(SixelEvent::new_dcs(None, None, None));
+    assert_eq!(size(extent), (0, 0));
+}
+
+#[test]
+fn cells_round_up() {
+    assert_eq!(ImageExtent::new(21, 40).cells(10, 20), (3, 2));
+    assert_eq!(ImageExtent::new(0, 0).cells(10, 20), (0, 0));
+}
+
+#[test]
+fn image_that_fits_below_the_cursor() {
+    let screen = Screen::new(80, 24, 10, 20);
+    let placement = screen.place(ImageExtent::new(50, 60), cell(2, 5));
+    assert_eq!(
+        placement,
+        Placement {
+            cursor: cell(2, 8),
+            scroll: 0,
+            covered: CellRect {
+                column: 2,
+                row: 5,
+                columns: 5,
+                rows: 3
+            },
+        }
+    );
+}
+
+#[test]
+fn cursor_on_the_line_below_the_image_scrolls() {
+    // the image ends on the last line, the cursor needs one more
+    let screen = Screen::new(80, 24, 10, 20);
+    let placement = screen.place(ImageExtent::new(10, 40), cell(0, 22));
+    assert_eq!(placement.scroll, 1);
+    assert_eq!(placement.cursor, cell(0, 23));
+    assert_eq!(placement.covered.row, 21);
+    assert_eq!(placement.covered.rows, 2);
+}
+
+#[test]
+fn cursor_on_the_last_line_of_the_image() {
+    let mut screen = Screen::new(80, 24, 10, 20);
+    screen.cursor_after_image = CursorAfterImage::LastLine;
+    let placement = screen.place(ImageExtent::new(10, 40), cell(3, 22));
+    assert_eq!(placement.scroll, 0);
+    assert_eq!(placement.cursor, cell(3, 23));
+    // an image that doesn't cover a full line leaves the cursor where it is
+    let placement = screen.place(ImageExtent::new(10, 1), cell(3, 22));
+    assert_eq!(placement.cursor, cell(3, 22));
+}
+
+#[test]
+fn cursor_right_of_the_image() {
+    let mut screen = Screen::new(80, 24, 10, 20);
+    screen.cursor_after_image = CursorAfterImage::RightOfImage;
+    let placement = screen.place(ImageExtent::new(30, 60), cell(10, 0));
+    assert_eq!(placement.scroll, 0);
+    assert_eq!(placement.cursor, cell(13, 2));
+    // at most the last column
+    let placement = screen.place(ImageExtent::new(300, 20), cell(75, 0));
+    assert_eq!(placement.cursor, cell(79, 0));
+    assert_eq!(placement.covered.columns, 5);
+}
+
+#[test]
+fn image_taller_than_the_screen() {
+    let screen = Screen::new(80, 24, 10, 20);
+    let placement = screen.place(ImageExtent::new(10, 20 * 30), cell(0, 4));
+    // the image runs 10 lines past the bottom and the cursor one more
+    assert_eq!(placement.scroll, 11);
+    assert_eq!(placement.cursor, cell(0, 23));
+    assert_eq!(
+        placement.covered,
+        CellRect {
+            column: 0,
+            row: 0,
+            columns: 1,
+            rows: 23
+        }
+    );
+}
+
+#[test]
+fn empty_image_doesnt_move_the_cursor() {
+    let screen = Screen::new(80, 24, 10, 20);
+    let placement = screen.place(ImageExtent::default(), cell(7, 23));
+    assert_eq!(placement.scroll, 0);
+    assert_eq!(placement.cursor, cell(7, 23));
+    assert_eq!(placement.covered.rows, 0);
+}
+
+#[test]
+fn sixel_display_mode_draws_at_the_top_left() {
+    let mut screen = Screen::new(80, 24, 10, 20);
+    screen.sixel_scrolling = false;
+    let placement = screen.place(ImageExtent::new(1000, 1000), cell(5, 23));
+    assert_eq!(
+        placement,
+        Placement {
+            cursor: cell(5, 23),
+            scroll: 0,
+            covered: CellRect {
+                column: 0,
+                row: 0,
+                columns: 80,
+                rows: 24
+            },
+        }
+    );
+}
+
+#[test]
+fn covered_cells() {
+    let covered = CellRect {
+        column: 2,
+        row: 1,
+        columns: 2,
+        rows: 1,
+    };
+    assert!(covered.contains(cell(3, 1)));
+    assert!(!covered.contains(cell(4, 1)));
+    assert!(!covered.contains(cell(2, 2)));
+}
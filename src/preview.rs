@@ -0,0 +1,123 @@
+//! Renders [`Bitmap`]s as text, for looking at images where sixel can't be displayed (such as CI
+//! logs or snapshot tests).
+//!
+//! Images are first stretched to square pixels and then scaled down to fit the given number of
+//! columns (they are never scaled up). Each character cell stands for an area twice as tall as it
+//! is wide, roughly the shape of a terminal cell.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::decoder::decode;
+//! use sixel_tokenizer::preview::{render, PreviewStyle};
+//!
+//! let image = decode(b"\x1bPq\"1;1;2;4#1;2;100;100;100!2B\x1b\\").remove(0);
+//! assert_eq!(render(&image, 2, PreviewStyle::Ascii), "@@\n  \n");
+//! ```
+
+use std::fmt::Write;
+
+use crate::{Bitmap, Limits};
+
+// from transparent or black to white
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewStyle {
+    /// `▀` and `▄` characters with 24-bit ANSI colors, showing two pixels per cell.
+    HalfBlocks,
+    /// Plain ASCII characters, denser ones for brighter (and more opaque) areas.
+    Ascii,
+}
+
+/// Renders the bitmap in at most `columns` columns, with a newline after each line. Bitmaps whose
+/// aspect ratio can't be corrected within the default [`Limits`] are rendered as they are.
+pub fn render(bitmap: &Bitmap, columns: usize, style: PreviewStyle) -> String {
+    let bitmap = bitmap
+        .correct_aspect_ratio(&Limits::default())
+        .unwrap_or_else(|| bitmap.clone());
+    let columns = std::cmp::min(std::cmp::max(columns, 1), bitmap.width);
+    if columns == 0 || bitmap.height == 0 {
+        return String::new();
+    }
+    // square pixels of the scaled down image
+    let rows = (bitmap.height * columns).div_ceil(bitmap.width);
+    let pixel = |x: usize, y: usize| {
+        if y < rows {
+            average(&bitmap, columns, x, y)
+        } else {
+            None
+        }
+    };
+    let mut preview = String::new();
+    for line in 0..rows.div_ceil(2) {
+        for x in 0..columns {
+            let top = pixel(x, line * 2);
+            let bottom = pixel(x, line * 2 + 1);
+            match style {
+                PreviewStyle::HalfBlocks => write_half_block(&mut preview, top, bottom),
+                PreviewStyle::Ascii => write_ascii(&mut preview, top, bottom),
+            }
+        }
+        if style == PreviewStyle::HalfBlocks {
+            preview.push_str("\x1b[0m");
+        }
+        preview.push('\n');
+    }
+    preview
+}
+
+// the average color of the pixels that scale down to (x, y), or `None` if they are mostly
+// transparent
+fn average(bitmap: &Bitmap, columns: usize, x: usize, y: usize) -> Option<[u8; 3]> {
+    let source = |i: usize, len: usize| {
+        let start = std::cmp::min(i * bitmap.width / columns, len - 1);
+        let end = ((i + 1) * bitmap.width / columns).clamp(start + 1, len);
+        start..end
+    };
+    let (xs, ys) = (source(x, bitmap.width), source(y, bitmap.height));
+    let mut sums = [0; 4];
+    for y in ys.clone() {
+        for x in xs.clone() {
+            let [r, g, b, a] = bitmap.pixel(x, y).map(usize::from);
+            sums[0] += r * a;
+            sums[1] += g * a;
+            sums[2] += b * a;
+            sums[3] += a;
+        }
+    }
+    let alpha = sums[3];
+    if alpha * 2 < xs.len() * ys.len() * 255 {
+        return None;
+    }
+    Some([0, 1, 2].map(|c| ((sums[c] + alpha / 2) / alpha) as u8))
+}
+
+fn write_half_block(preview: &mut String, top: Option<[u8; 3]>, bottom: Option<[u8; 3]>) {
+    // writing to a String can't fail
+    let _ = match (top, bottom) {
+        (Some([r, g, b]), Some([br, bg, bb])) => write!(
+            preview,
+            "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+            r, g, b, br, bg, bb
+        ),
+        (Some([r, g, b]), None) => write!(preview, "\x1b[38;2;{};{};{};49m▀", r, g, b),
+        (None, Some([r, g, b])) => write!(preview, "\x1b[38;2;{};{};{};49m▄", r, g, b),
+        (None, None) => write!(preview, "\x1b[0m "),
+    };
+}
+
+fn write_ascii(preview: &mut String, top: Option<[u8; 3]>, bottom: Option<[u8; 3]>) {
+    // transparent pixels count as black
+    let luminance = |pixel: Option<[u8; 3]>| {
+        pixel.map_or(0, |[r, g, b]| {
+            2126 * r as usize + 7152 * g as usize + 722 * b as usize
+        })
+    };
+    let luminance = (luminance(top) + luminance(bottom)) / 2;
+    let index = (luminance * (ASCII_RAMP.len() - 1) + 255 * 10000 / 2) / (255 * 10000);
+    preview.push(ASCII_RAMP[index] as char);
+}
+
+#[cfg(test)]
+#[path = "./preview_tests.rs"]
+mod tests;
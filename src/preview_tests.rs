@@ -0,0 +1,95 @@
+use super::*;
+use insta::assert_snapshot;
+
+use crate::decoder::decode;
+use crate::Encoder;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const RED: [u8; 4] = [255, 0, 0, 255];
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+fn bitmap(width: usize, height: usize, color: impl Fn(usize, usize) -> [u8; 4]) -> Bitmap {
+    let mut bitmap = Bitmap::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            bitmap.set_pixel(x, y, color(x, y));
+        }
+    }
+    bitmap
+}
+
+#[test]
+fn ascii_ramp() {
+    let gradient = bitmap(10, 2, |x, _| {
+        let level = (x * 255 / 9) as u8;
+        [level, level, level, 255]
+    });
+    assert_eq!(render(&gradient, 10, PreviewStyle::Ascii), " .:-=+*#%@\n");
+}
+
+#[test]
+fn ascii_scales_down() {
+    // a white square in the top left quarter
+    let image = bitmap(
+        8,
+        8,
+        |x, y| if x < 4 && y < 4 { WHITE } else { TRANSPARENT },
+    );
+    assert_eq!(render(&image, 4, PreviewStyle::Ascii), "@@  \n    \n");
+    assert_eq!(render(&image, 100, PreviewStyle::Ascii).lines().count(), 4);
+}
+
+#[test]
+fn pixel_aspect_ratio_is_corrected() {
+    let mut image = bitmap(2, 2, |_, _| WHITE);
+    image.pixel_aspect_ratio = (2, 1);
+    assert_eq!(render(&image, 2, PreviewStyle::Ascii), "@@\n@@\n");
+}
+
+#[test]
+fn half_blocks() {
+    let image = bitmap(3, 2, |x, y| match (x, y) {
+        (0, _) => RED,
+        (1, 0) | (2, 1) => WHITE,
+        _ => TRANSPARENT,
+    });
+    assert_eq!(
+        render(&image, 3, PreviewStyle::HalfBlocks),
+        "\x1b[38;2;255;0;0;48;2;255;0;0m▀\
+         \x1b[38;2;255;255;255;49m▀\
+         \x1b[38;2;255;255;255;49m▄\
+         \x1b[0m\n"
+    );
+}
+
+#[test]
+fn transparent_half_blocks() {
+    let image = bitmap(1, 1, |_, _| TRANSPARENT);
+    assert_eq!(
+        render(&image, 1, PreviewStyle::HalfBlocks),
+        "\x1b[0m \x1b[0m\n"
+    );
+}
+
+#[test]
+fn empty_bitmap() {
+    assert_eq!(render(&Bitmap::new(0, 3), 10, PreviewStyle::Ascii), "");
+}
+
+#[test]
+fn snapshot_of_decoded_image() {
+    // a filled circle that fades from white at the top to black at the bottom
+    let circle = bitmap(32, 32, |x, y| {
+        let (dx, dy) = (x as isize * 2 - 31, y as isize * 2 - 31);
+        let level = 255 - (y * 255 / 31) as u8;
+        if dx * dx + dy * dy <= 30 * 30 {
+            [level, level, level, 255]
+        } else {
+            TRANSPARENT
+        }
+    });
+    let mut sixel = vec![];
+    Encoder::new().encode_to(&circle, &mut sixel).unwrap();
+    let decoded = decode(&sixel).remove(0);
+    assert_snapshot!(render(&decoded, 24, PreviewStyle::Ascii));
+}
@@ -0,0 +1,388 @@
+//! Queries for sixel support and the replies to them, for applications (which send queries and
+//! parse replies) as well as for terminal emulators (which parse queries and send replies).
+//!
+//! - Primary device attributes (DA1, `CSI c`) are answered with `CSI ? Ps ; ... c`, where an
+//!   attribute of 4 means the terminal supports sixel.
+//! - XTSMGRAPHICS (`CSI ? Pi ; Pa ; Pv S`) reads or changes the number of color registers and the
+//!   maximum sixel geometry. These correspond to [`Limits::color_registers`] and
+//!   [`Limits::max_width`] / [`Limits::max_height`]: [`Limits::answer`] replies to a query and
+//!   [`Limits::apply`] updates the limits from a reply. An emulator that lets applications change
+//!   its limits passes the new ones to [`Decoder::set_limits`](crate::Decoder::set_limits).
+//!
+//! Only 7-bit sequences are recognized, and each function is given one complete sequence.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::query::{GraphicsItem, GraphicsQuery, GraphicsReply};
+//! use sixel_tokenizer::Limits;
+//!
+//! // the emulator
+//! let mut limits = Limits::default();
+//! let query = GraphicsQuery::parse(b"\x1b[?1;1;0S").unwrap();
+//! let mut reply = vec![];
+//! limits.answer(query, &Limits::default()).write_to(&mut reply).unwrap();
+//! assert_eq!(reply, b"\x1b[?1;0;1024S");
+//!
+//! // the application
+//! let mut client_limits = Limits { color_registers: 256, ..Limits::default() };
+//! let reply = GraphicsReply::parse(&reply).unwrap();
+//! assert_eq!(reply.item, GraphicsItem::ColorRegisters);
+//! assert!(client_limits.apply(&reply));
+//! assert_eq!(client_limits.color_registers, 1024);
+//! ```
+
+use std::io::{self, Write};
+
+use crate::Limits;
+
+/// The primary device attributes query (DA1).
+pub const DEVICE_ATTRIBUTES_QUERY: &[u8] = b"\x1b[c";
+/// The attribute a terminal reports in its DA1 reply if it supports sixel.
+pub const SIXEL_ATTRIBUTE: usize = 4;
+
+/// Whether `bytes` is a DA1 query (`CSI c` or `CSI 0 c`).
+pub fn is_device_attributes_query(bytes: &[u8]) -> bool {
+    bytes == b"\x1b[c" || bytes == b"\x1b[0c"
+}
+
+/// A reply to the DA1 query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceAttributes {
+    /// The operating level (such as 62 for a VT220 or 63 for a VT340) followed by the attributes.
+    pub attributes: Vec<usize>,
+}
+
+impl DeviceAttributes {
+    /// Parses a `CSI ? Ps ; ... c` reply.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let attributes = parse_parameters(bytes, b'c')?
+            .into_iter()
+            .collect::<Option<Vec<_>>>()?;
+        if attributes.is_empty() {
+            return None;
+        }
+        Some(DeviceAttributes { attributes })
+    }
+    pub fn supports_sixel(&self) -> bool {
+        self.attributes
+            .get(1..)
+            .is_some_and(|attributes| attributes.contains(&SIXEL_ATTRIBUTE))
+    }
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(writer, "\x1b[?")?;
+        write_parameters(writer, &self.attributes)?;
+        write!(writer, "c")
+    }
+}
+
+/// The graphics attribute (`Pi`) of an XTSMGRAPHICS query or reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsItem {
+    /// The number of color registers, 1.
+    ColorRegisters,
+    /// The maximum width and height of sixel images in pixels, 2.
+    SixelGeometry,
+    /// The maximum width and height of ReGIS graphics in pixels, 3.
+    RegisGeometry,
+    Other(usize),
+}
+
+impl GraphicsItem {
+    fn from_parameter(parameter: usize) -> Self {
+        match parameter {
+            1 => GraphicsItem::ColorRegisters,
+            2 => GraphicsItem::SixelGeometry,
+            3 => GraphicsItem::RegisGeometry,
+            _ => GraphicsItem::Other(parameter),
+        }
+    }
+    fn parameter(&self) -> usize {
+        match *self {
+            GraphicsItem::ColorRegisters => 1,
+            GraphicsItem::SixelGeometry => 2,
+            GraphicsItem::RegisGeometry => 3,
+            GraphicsItem::Other(parameter) => parameter,
+        }
+    }
+}
+
+/// The value of a [`GraphicsItem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsValue {
+    ColorRegisters(usize),
+    Geometry { width: usize, height: usize },
+}
+
+impl GraphicsValue {
+    fn parameters(&self) -> Vec<usize> {
+        match *self {
+            GraphicsValue::ColorRegisters(registers) => vec![registers],
+            GraphicsValue::Geometry { width, height } => vec![width, height],
+        }
+    }
+    fn from_parameters(item: GraphicsItem, parameters: &[Option<usize>]) -> Option<Self> {
+        match (item, parameters) {
+            (GraphicsItem::ColorRegisters, [Some(registers), ..]) => {
+                Some(GraphicsValue::ColorRegisters(*registers))
+            }
+            (
+                GraphicsItem::SixelGeometry | GraphicsItem::RegisGeometry,
+                [Some(width), Some(height), ..],
+            ) => Some(GraphicsValue::Geometry {
+                width: *width,
+                height: *height,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// What an XTSMGRAPHICS query asks for (`Pa`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsAction {
+    /// Read the current value, 1.
+    Read,
+    /// Reset to the default value, 2.
+    Reset,
+    /// Set to a value, 3. The value is `None` if it is missing or doesn't fit the item.
+    Set(Option<GraphicsValue>),
+    /// Read the largest value that can be set, 4.
+    ReadMaximum,
+    Other(usize),
+}
+
+/// An XTSMGRAPHICS query (`CSI ? Pi ; Pa ; Pv S`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GraphicsQuery {
+    pub item: GraphicsItem,
+    pub action: GraphicsAction,
+}
+
+impl GraphicsQuery {
+    pub fn read(item: GraphicsItem) -> Self {
+        GraphicsQuery {
+            item,
+            action: GraphicsAction::Read,
+        }
+    }
+    pub fn read_maximum(item: GraphicsItem) -> Self {
+        GraphicsQuery {
+            item,
+            action: GraphicsAction::ReadMaximum,
+        }
+    }
+    /// Parses a query. Returns `None` if `bytes` is not an XTSMGRAPHICS query.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let parameters = parse_parameters(bytes, b'S')?;
+        let item = GraphicsItem::from_parameter(parameters.first().copied()??);
+        let action = match parameters.get(1).copied()?? {
+            1 => GraphicsAction::Read,
+            2 => GraphicsAction::Reset,
+            3 => GraphicsAction::Set(GraphicsValue::from_parameters(item, &parameters[2..])),
+            4 => GraphicsAction::ReadMaximum,
+            action => GraphicsAction::Other(action),
+        };
+        Some(GraphicsQuery { item, action })
+    }
+    /// Writes the query. `Read` and `ReadMaximum` queries end with a `Pv` of 0, as is usual.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut parameters = vec![self.item.parameter()];
+        match self.action {
+            GraphicsAction::Read => parameters.extend([1, 0]),
+            GraphicsAction::Reset => parameters.push(2),
+            GraphicsAction::Set(value) => {
+                parameters.push(3);
+                parameters.extend(value.map(|value| value.parameters()).unwrap_or_default());
+            }
+            GraphicsAction::ReadMaximum => parameters.extend([4, 0]),
+            GraphicsAction::Other(action) => parameters.push(action),
+        }
+        write!(writer, "\x1b[?")?;
+        write_parameters(writer, &parameters)?;
+        write!(writer, "S")
+    }
+}
+
+/// The status (`Ps`) of an XTSMGRAPHICS reply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsStatus {
+    /// 0
+    Success,
+    /// 1, the item is not supported.
+    UnknownItem,
+    /// 2, the action is not supported.
+    UnknownAction,
+    /// 3, the action failed.
+    Failure,
+}
+
+/// An XTSMGRAPHICS reply (`CSI ? Pi ; Ps ; Pv S`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GraphicsReply {
+    pub item: GraphicsItem,
+    pub status: GraphicsStatus,
+    /// The value of the item, for successful replies.
+    pub value: Option<GraphicsValue>,
+}
+
+impl GraphicsReply {
+    /// Parses a reply. The value of an unsuccessful reply is ignored.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let parameters = parse_parameters(bytes, b'S')?;
+        let item = GraphicsItem::from_parameter(parameters.first().copied()??);
+        let status = match parameters.get(1).copied()?? {
+            0 => GraphicsStatus::Success,
+            1 => GraphicsStatus::UnknownItem,
+            2 => GraphicsStatus::UnknownAction,
+            3 => GraphicsStatus::Failure,
+            _ => return None,
+        };
+        let value = match status {
+            GraphicsStatus::Success => {
+                Some(GraphicsValue::from_parameters(item, &parameters[2..])?)
+            }
+            _ => None,
+        };
+        Some(GraphicsReply {
+            item,
+            status,
+            value,
+        })
+    }
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let status = match self.status {
+            GraphicsStatus::Success => 0,
+            GraphicsStatus::UnknownItem => 1,
+            GraphicsStatus::UnknownAction => 2,
+            GraphicsStatus::Failure => 3,
+        };
+        let mut parameters = vec![self.item.parameter(), status];
+        parameters.extend(
+            self.value
+                .map(|value| value.parameters())
+                .unwrap_or_default(),
+        );
+        write!(writer, "\x1b[?")?;
+        write_parameters(writer, &parameters)?;
+        write!(writer, "S")
+    }
+}
+
+impl Limits {
+    /// Answers an XTSMGRAPHICS query as an emulator whose decoder uses these limits, changing
+    /// them if the query asks to. Values can be set up to `maximum` and are reset to the values
+    /// of [`Limits::default`] (but no more than `maximum`). ReGIS is not supported.
+    pub fn answer(&mut self, query: GraphicsQuery, maximum: &Limits) -> GraphicsReply {
+        let error = |status| GraphicsReply {
+            item: query.item,
+            status,
+            value: None,
+        };
+        if !matches!(
+            query.item,
+            GraphicsItem::ColorRegisters | GraphicsItem::SixelGeometry
+        ) {
+            return error(GraphicsStatus::UnknownItem);
+        }
+        match query.action {
+            GraphicsAction::Read => {}
+            GraphicsAction::ReadMaximum => {
+                return GraphicsReply {
+                    item: query.item,
+                    status: GraphicsStatus::Success,
+                    value: Some(maximum.value(query.item)),
+                };
+            }
+            GraphicsAction::Reset => {
+                let default = Limits::default();
+                match query.item {
+                    GraphicsItem::ColorRegisters => {
+                        self.color_registers =
+                            std::cmp::min(default.color_registers, maximum.color_registers);
+                    }
+                    _ => {
+                        self.max_width = std::cmp::min(default.max_width, maximum.max_width);
+                        self.max_height = std::cmp::min(default.max_height, maximum.max_height);
+                    }
+                }
+            }
+            GraphicsAction::Set(Some(GraphicsValue::ColorRegisters(registers))) => {
+                if registers == 0 {
+                    return error(GraphicsStatus::Failure);
+                }
+                self.color_registers = std::cmp::min(registers, maximum.color_registers);
+            }
+            GraphicsAction::Set(Some(GraphicsValue::Geometry { width, height })) => {
+                if width == 0 || height == 0 {
+                    return error(GraphicsStatus::Failure);
+                }
+                self.max_width = std::cmp::min(width, maximum.max_width);
+                self.max_height = std::cmp::min(height, maximum.max_height);
+            }
+            GraphicsAction::Set(None) => return error(GraphicsStatus::Failure),
+            GraphicsAction::Other(_) => return error(GraphicsStatus::UnknownAction),
+        }
+        GraphicsReply {
+            item: query.item,
+            status: GraphicsStatus::Success,
+            value: Some(self.value(query.item)),
+        }
+    }
+    /// Updates these limits from a successful XTSMGRAPHICS reply. Returns false if the reply
+    /// was unsuccessful or is about ReGIS.
+    pub fn apply(&mut self, reply: &GraphicsReply) -> bool {
+        match (reply.item, reply.value) {
+            (GraphicsItem::ColorRegisters, Some(GraphicsValue::ColorRegisters(registers))) => {
+                self.color_registers = registers;
+                true
+            }
+            (GraphicsItem::SixelGeometry, Some(GraphicsValue::Geometry { width, height })) => {
+                self.max_width = width;
+                self.max_height = height;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn value(&self, item: GraphicsItem) -> GraphicsValue {
+        match item {
+            GraphicsItem::ColorRegisters => GraphicsValue::ColorRegisters(self.color_registers),
+            _ => GraphicsValue::Geometry {
+                width: self.max_width,
+                height: self.max_height,
+            },
+        }
+    }
+}
+
+// the parameters of `CSI ? Ps ; ... final`, an empty parameter is `None`
+fn parse_parameters(bytes: &[u8], final_byte: u8) -> Option<Vec<Option<usize>>> {
+    let parameters = bytes.strip_prefix(b"\x1b[?")?.strip_suffix(&[final_byte])?;
+    if !parameters
+        .iter()
+        .all(|byte| byte.is_ascii_digit() || *byte == b';')
+    {
+        return None;
+    }
+    parameters
+        .split(|byte| *byte == b';')
+        .map(|parameter| match parameter {
+            [] => Some(None),
+            _ => std::str::from_utf8(parameter).ok()?.parse().ok().map(Some),
+        })
+        .collect()
+}
+
+fn write_parameters(writer: &mut impl Write, parameters: &[usize]) -> io::Result<()> {
+    for (i, parameter) in parameters.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ";")?;
+        }
+        write!(writer, "{}", parameter)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "./query_tests.rs"]
+mod tests;
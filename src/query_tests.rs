@@ -0,0 +1,239 @@
+use super::*;
+
+fn bytes(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+    let mut bytes = vec![];
+    write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn device_attributes_with_sixel() {
+    let attributes = DeviceAttributes::parse(b"\x1b[?62;4;6;22c").unwrap();
+    assert_eq!(attributes.attributes, vec![62, 4, 6, 22]);
+    assert!(attributes.supports_sixel());
+    assert_eq!(bytes(|w| attributes.write_to(w)), b"\x1b[?62;4;6;22c");
+}
+
+#[test]
+fn device_attributes_without_sixel() {
+    // the operating level is not an attribute
+    let attributes = DeviceAttributes::parse(b"\x1b[?4;1;2c").unwrap();
+    assert!(!attributes.supports_sixel());
+    assert!(!DeviceAttributes::parse(b"\x1b[?1;2c")
+        .unwrap()
+        .supports_sixel());
+    let attributes = DeviceAttributes { attributes: vec![] };
+    assert!(!attributes.supports_sixel());
+}
+
+#[test]
+fn invalid_device_attributes() {
+    assert_eq!(DeviceAttributes::parse(b"\x1b[?c"), None);
+    assert_eq!(DeviceAttributes::parse(b"\x1b[?62;;4c"), None);
+    assert_eq!(DeviceAttributes::parse(b"\x1b[62;4c"), None);
+    assert_eq!(DeviceAttributes::parse(b"\x1b[?62;4x"), None);
+    assert_eq!(DeviceAttributes::parse(b"\x1b[?62;4c\x1b[?62c"), None);
+}
+
+#[test]
+fn device_attributes_query() {
+    assert!(is_device_attributes_query(DEVICE_ATTRIBUTES_QUERY));
+    assert!(is_device_attributes_query(b"\x1b[0c"));
+    assert!(!is_device_attributes_query(b"\x1b[>c"));
+}
+
+#[test]
+fn graphics_queries_round_trip() {
+    let queries: [(&[u8], GraphicsQuery); 5] = [
+        (
+            b"\x1b[?1;1;0S",
+            GraphicsQuery::read(GraphicsItem::ColorRegisters),
+        ),
+        (
+            b"\x1b[?2;4;0S",
+            GraphicsQuery::read_maximum(GraphicsItem::SixelGeometry),
+        ),
+        (
+            b"\x1b[?1;2S",
+            GraphicsQuery {
+                item: GraphicsItem::ColorRegisters,
+                action: GraphicsAction::Reset,
+            },
+        ),
+        (
+            b"\x1b[?2;3;640;480S",
+            GraphicsQuery {
+                item: GraphicsItem::SixelGeometry,
+                action: GraphicsAction::Set(Some(GraphicsValue::Geometry {
+                    width: 640,
+                    height: 480,
+                })),
+            },
+        ),
+        (
+            b"\x1b[?7;9S",
+            GraphicsQuery {
+                item: GraphicsItem::Other(7),
+                action: GraphicsAction::Other(9),
+            },
+        ),
+    ];
+    for (sequence, query) in queries {
+        assert_eq!(GraphicsQuery::parse(sequence), Some(query));
+        assert_eq!(bytes(|w| query.write_to(w)), sequence);
+    }
+}
+
+#[test]
+fn read_queries_without_a_value() {
+    assert_eq!(
+        GraphicsQuery::parse(b"\x1b[?1;1S"),
+        Some(GraphicsQuery::read(GraphicsItem::ColorRegisters))
+    );
+}
+
+#[test]
+fn set_query_with_a_missing_value() {
+    let query = GraphicsQuery::parse(b"\x1b[?2;3;640S").unwrap();
+    assert_eq!(query.action, GraphicsAction::Set(None));
+}
+
+#[test]
+fn graphics_replies_round_trip() {
+    let replies: [(&[u8], GraphicsReply); 3] = [
+        (
+            b"\x1b[?1;0;256S",
+            GraphicsReply {
+                item: GraphicsItem::ColorRegisters,
+                status: GraphicsStatus::Success,
+                value: Some(GraphicsValue::ColorRegisters(256)),
+            },
+        ),
+        (
+            b"\x1b[?2;0;1000;1000S",
+            GraphicsReply {
+                item: GraphicsItem::SixelGeometry,
+                status: GraphicsStatus::Success,
+                value: Some(GraphicsValue::Geometry {
+                    width: 1000,
+                    height: 1000,
+                }),
+            },
+        ),
+        (
+            b"\x1b[?3;1S",
+            GraphicsReply {
+                item: GraphicsItem::RegisGeometry,
+                status: GraphicsStatus::UnknownItem,
+                value: None,
+            },
+        ),
+    ];
+    for (sequence, reply) in replies {
+        assert_eq!(GraphicsReply::parse(sequence), Some(reply));
+        assert_eq!(bytes(|w| reply.write_to(w)), sequence);
+    }
+}
+
+#[test]
+fn value_of_failed_reply_is_ignored() {
+    let reply = GraphicsReply::parse(b"\x1b[?1;3;0S").unwrap();
+    assert_eq!(reply.status, GraphicsStatus::Failure);
+    assert_eq!(reply.value, None);
+}
+
+#[test]
+fn invalid_graphics_replies() {
+    assert_eq!(GraphicsReply::parse(b"\x1b[?1;0S"), None);
+    assert_eq!(GraphicsReply::parse(b"\x1b[?2;0;100S"), None);
+    assert_eq!(GraphicsReply::parse(b"\x1b[?1;7;0S"), None);
+    assert_eq!(GraphicsReply::parse(b"\x1b[?1S"), None);
+}
+
+#[test]
+fn answer_reads() {
+    let maximum = Limits {
+        max_width: 8192,
+        max_height: 8192,
+        color_registers: 4096,
+    };
+    let mut limits = Limits::default();
+    let reply = limits.answer(GraphicsQuery::read(GraphicsItem::SixelGeometry), &maximum);
+    assert_eq!(
+        reply.value,
+        Some(GraphicsValue::Geometry {
+            width: 4096,
+            height: 4096
+        })
+    );
+    let query = GraphicsQuery::read_maximum(GraphicsItem::ColorRegisters);
+    let reply = limits.answer(query, &maximum);
+    assert_eq!(reply.value, Some(GraphicsValue::ColorRegisters(4096)));
+    assert_eq!(limits, Limits::default());
+}
+
+#[test]
+fn answer_sets_within_the_maximum() {
+    let maximum = Limits::default();
+    let mut limits = Limits::default();
+    let query = GraphicsQuery::parse(b"\x1b[?1;3;100000S").unwrap();
+    let reply = limits.answer(query, &maximum);
+    assert_eq!(reply.status, GraphicsStatus::Success);
+    assert_eq!(reply.value, Some(GraphicsValue::ColorRegisters(1024)));
+    let query = GraphicsQuery::parse(b"\x1b[?2;3;640;480S").unwrap();
+    limits.answer(query, &maximum);
+    assert_eq!(
+        limits,
+        Limits {
+            max_width: 640,
+            max_height: 480,
+            color_registers: 1024
+        }
+    );
+    let query = GraphicsQuery::parse(b"\x1b[?2;2S").unwrap();
+    limits.answer(query, &maximum);
+    assert_eq!(limits, Limits::default());
+}
+
+#[test]
+fn answer_errors() {
+    let maximum = Limits::default();
+    let mut limits = Limits::default();
+    let status = |limits: &mut Limits, query: &[u8]| {
+        let reply = limits.answer(GraphicsQuery::parse(query).unwrap(), &maximum);
+        assert_eq!(reply.value, None);
+        reply.status
+    };
+    assert_eq!(
+        status(&mut limits, b"\x1b[?3;1;0S"),
+        GraphicsStatus::UnknownItem
+    );
+    assert_eq!(
+        status(&mut limits, b"\x1b[?9;1;0S"),
+        GraphicsStatus::UnknownItem
+    );
+    assert_eq!(
+        status(&mut limits, b"\x1b[?1;5;0S"),
+        GraphicsStatus::UnknownAction
+    );
+    assert_eq!(
+        status(&mut limits, b"\x1b[?1;3;0S"),
+        GraphicsStatus::Failure
+    );
+    assert_eq!(
+        status(&mut limits, b"\x1b[?2;3;10S"),
+        GraphicsStatus::Failure
+    );
+    assert_eq!(limits, Limits::default());
+}
+
+#[test]
+fn apply_replies() {
+    let mut limits = Limits::default();
+    let reply = GraphicsReply::parse(b"\x1b[?2;0;800;600S").unwrap();
+    assert!(limits.apply(&reply));
+    assert_eq!((limits.max_width, limits.max_height), (800, 600));
+    let reply = GraphicsReply::parse(b"\x1b[?1;3S").unwrap();
+    assert!(!limits.apply(&reply));
+    assert_eq!(limits.color_registers, 1024);
+}
@@ -0,0 +1,213 @@
+//! Vectorized byte classification for sixel data.
+//!
+//! Most of the bytes of a sixel image are sixel data (`?` to `~`), so finding where a run of data
+//! ends is the hot loop of [`Parser::advance_slice`](crate::Parser::advance_slice). These functions
+//! look at 16 or 32 bytes at a time using SSE2 or AVX2 (detected at runtime) on x86_64 and NEON on
+//! aarch64, and fall back to a byte-by-byte scan elsewhere. All implementations give identical
+//! results.
+
+const ESC: u8 = 27;
+
+/// Bytes that start an instruction or end the image: `!`, `"`, `#`, `$`, `-` and ESC.
+pub const SPECIAL_BYTES: [u8; 6] = [b'!', b'"', b'#', b'$', b'-', ESC];
+
+#[cfg(target_arch = "x86_64")]
+use x86 as arch;
+
+#[cfg(target_arch = "aarch64")]
+use neon as arch;
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+use scalar as arch;
+
+/// The number of sixel data bytes at the start of `bytes`.
+pub fn data_run_length(bytes: &[u8]) -> usize {
+    arch::data_run_length(bytes)
+}
+
+/// The index of the first of [`SPECIAL_BYTES`] in `bytes`.
+pub fn find_special_byte(bytes: &[u8]) -> Option<usize> {
+    arch::find_special_byte(bytes)
+}
+
+fn is_sixel_data(byte: u8) -> bool {
+    (b'?'..=b'~').contains(&byte)
+}
+
+mod scalar {
+    use super::{is_sixel_data, SPECIAL_BYTES};
+
+    pub fn data_run_length(bytes: &[u8]) -> usize {
+        bytes
+            .iter()
+            .position(|byte| !is_sixel_data(*byte))
+            .unwrap_or(bytes.len())
+    }
+    pub fn find_special_byte(bytes: &[u8]) -> Option<usize> {
+        bytes.iter().position(|byte| SPECIAL_BYTES.contains(byte))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{scalar, SPECIAL_BYTES};
+
+    pub fn data_run_length(bytes: &[u8]) -> usize {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2
+            unsafe { data_run_length_avx2(bytes) }
+        } else {
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe { data_run_length_sse2(bytes) }
+        }
+    }
+    pub fn find_special_byte(bytes: &[u8]) -> Option<usize> {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2
+            unsafe { find_special_byte_avx2(bytes) }
+        } else {
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe { find_special_byte_sse2(bytes) }
+        }
+    }
+
+    // adding 0x41 moves `?`..=`~` to 0x80..=0xBF, which are the only bytes below -64 as an i8
+    const DATA_OFFSET: i8 = 0x41;
+    const DATA_LIMIT: i8 = -64;
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn data_run_length_sse2(bytes: &[u8]) -> usize {
+        let mut offset = 0;
+        while offset + 16 <= bytes.len() {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().add(offset) as *const __m128i);
+            let shifted = _mm_add_epi8(chunk, _mm_set1_epi8(DATA_OFFSET));
+            let is_data = _mm_cmplt_epi8(shifted, _mm_set1_epi8(DATA_LIMIT));
+            let not_data = !(_mm_movemask_epi8(is_data) as u32) & 0xFFFF;
+            if not_data != 0 {
+                return offset + not_data.trailing_zeros() as usize;
+            }
+            offset += 16;
+        }
+        offset + scalar::data_run_length(&bytes[offset..])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn find_special_byte_sse2(bytes: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        while offset + 16 <= bytes.len() {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().add(offset) as *const __m128i);
+            let mut is_special = _mm_setzero_si128();
+            for special_byte in SPECIAL_BYTES {
+                let is_byte = _mm_cmpeq_epi8(chunk, _mm_set1_epi8(special_byte as i8));
+                is_special = _mm_or_si128(is_special, is_byte);
+            }
+            let special = _mm_movemask_epi8(is_special) as u32;
+            if special != 0 {
+                return Some(offset + special.trailing_zeros() as usize);
+            }
+            offset += 16;
+        }
+        scalar::find_special_byte(&bytes[offset..]).map(|index| offset + index)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn data_run_length_avx2(bytes: &[u8]) -> usize {
+        let mut offset = 0;
+        while offset + 32 <= bytes.len() {
+            let chunk = _mm256_loadu_si256(bytes.as_ptr().add(offset) as *const __m256i);
+            let shifted = _mm256_add_epi8(chunk, _mm256_set1_epi8(DATA_OFFSET));
+            let is_data = _mm256_cmpgt_epi8(_mm256_set1_epi8(DATA_LIMIT), shifted);
+            let not_data = !(_mm256_movemask_epi8(is_data) as u32);
+            if not_data != 0 {
+                return offset + not_data.trailing_zeros() as usize;
+            }
+            offset += 32;
+        }
+        offset + data_run_length_sse2(&bytes[offset..])
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn find_special_byte_avx2(bytes: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        while offset + 32 <= bytes.len() {
+            let chunk = _mm256_loadu_si256(bytes.as_ptr().add(offset) as *const __m256i);
+            let mut is_special = _mm256_setzero_si256();
+            for special_byte in SPECIAL_BYTES {
+                let is_byte = _mm256_cmpeq_epi8(chunk, _mm256_set1_epi8(special_byte as i8));
+                is_special = _mm256_or_si256(is_special, is_byte);
+            }
+            let special = _mm256_movemask_epi8(is_special) as u32;
+            if special != 0 {
+                return Some(offset + special.trailing_zeros() as usize);
+            }
+            offset += 32;
+        }
+        find_special_byte_sse2(&bytes[offset..]).map(|index| offset + index)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::{scalar, SPECIAL_BYTES};
+
+    pub fn data_run_length(bytes: &[u8]) -> usize {
+        // SAFETY: NEON is part of the aarch64 baseline
+        unsafe { data_run_length_neon(bytes) }
+    }
+    pub fn find_special_byte(bytes: &[u8]) -> Option<usize> {
+        // SAFETY: NEON is part of the aarch64 baseline
+        unsafe { find_special_byte_neon(bytes) }
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn data_run_length_neon(bytes: &[u8]) -> usize {
+        let mut offset = 0;
+        while offset + 16 <= bytes.len() {
+            let chunk = vld1q_u8(bytes.as_ptr().add(offset));
+            let is_data = vcltq_u8(vsubq_u8(chunk, vdupq_n_u8(b'?')), vdupq_n_u8(64));
+            if let Some(index) = first_set_lane(vmvnq_u8(is_data)) {
+                return offset + index;
+            }
+            offset += 16;
+        }
+        offset + scalar::data_run_length(&bytes[offset..])
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn find_special_byte_neon(bytes: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        while offset + 16 <= bytes.len() {
+            let chunk = vld1q_u8(bytes.as_ptr().add(offset));
+            let mut is_special = vdupq_n_u8(0);
+            for special_byte in SPECIAL_BYTES {
+                is_special = vorrq_u8(is_special, vceqq_u8(chunk, vdupq_n_u8(special_byte)));
+            }
+            if let Some(index) = first_set_lane(is_special) {
+                return Some(offset + index);
+            }
+            offset += 16;
+        }
+        scalar::find_special_byte(&bytes[offset..]).map(|index| offset + index)
+    }
+
+    // NEON has no movemask, so every lane is narrowed to four bits of a u64 instead
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn first_set_lane(mask: uint8x16_t) -> Option<usize> {
+        let nibbles = vshrn_n_u16(vreinterpretq_u16_u8(mask), 4);
+        let nibbles = vget_lane_u64(vreinterpret_u64_u8(nibbles), 0);
+        if nibbles == 0 {
+            None
+        } else {
+            Some(nibbles.trailing_zeros() as usize / 4)
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./scanner_tests.rs"]
+mod tests;
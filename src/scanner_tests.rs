@@ -0,0 +1,104 @@
+use super::*;
+
+type DataRunLength = fn(&[u8]) -> usize;
+type FindSpecialByte = fn(&[u8]) -> Option<usize>;
+
+fn implementations() -> Vec<(&'static str, DataRunLength, FindSpecialByte)> {
+    #[allow(unused_mut)]
+    let mut implementations: Vec<(&'static str, DataRunLength, FindSpecialByte)> =
+        vec![("dispatched", data_run_length, find_special_byte)];
+    #[cfg(target_arch = "x86_64")]
+    {
+        implementations.push((
+            "sse2",
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            |bytes| unsafe { x86::data_run_length_sse2(bytes) },
+            |bytes| unsafe { x86::find_special_byte_sse2(bytes) },
+        ));
+        if is_x86_feature_detected!("avx2") {
+            implementations.push((
+                "avx2",
+                // SAFETY: the CPU supports AVX2
+                |bytes| unsafe { x86::data_run_length_avx2(bytes) },
+                |bytes| unsafe { x86::find_special_byte_avx2(bytes) },
+            ));
+        }
+    }
+    implementations
+}
+
+fn assert_matches_scalar(bytes: &[u8]) {
+    for (name, data_run_length, find_special_byte) in implementations() {
+        assert_eq!(
+            data_run_length(bytes),
+            scalar::data_run_length(bytes),
+            "{} data_run_length({:?})",
+            name,
+            bytes
+        );
+        assert_eq!(
+            find_special_byte(bytes),
+            scalar::find_special_byte(bytes),
+            "{} find_special_byte({:?})",
+            name,
+            bytes
+        );
+    }
+}
+
+#[test]
+fn scalar_classification() {
+    assert_eq!(scalar::data_run_length(b"??~~@@!3a"), 6);
+    assert_eq!(scalar::data_run_length(b"~~~"), 3);
+    assert_eq!(scalar::data_run_length(b""), 0);
+    assert_eq!(scalar::find_special_byte(b"~~@@#1"), Some(4));
+    assert_eq!(scalar::find_special_byte(b"~~\x1b\\"), Some(2));
+    assert_eq!(scalar::find_special_byte(b"~~ ;0"), None);
+}
+
+#[test]
+fn every_byte_at_every_position() {
+    // lengths around the 16 and 32 byte chunks, so every byte is also tried in the scalar tail
+    for len in [1, 15, 16, 17, 31, 32, 33, 47, 48, 63, 64, 65, 70] {
+        for position in 0..len {
+            for byte in 0..=255 {
+                let mut bytes = vec![b'~'; len];
+                bytes[position] = byte;
+                assert_matches_scalar(&bytes);
+            }
+        }
+    }
+}
+
+#[test]
+fn every_byte_after_special_bytes() {
+    let mut bytes = vec![b'?'; 64];
+    bytes[40] = b'#';
+    for position in 0..64 {
+        for byte in 0..=255 {
+            let mut bytes = bytes.clone();
+            bytes[position] = byte;
+            assert_matches_scalar(&bytes);
+        }
+    }
+}
+
+#[test]
+fn pseudo_random_buffers() {
+    let mut seed: u32 = 1;
+    for _ in 0..2000 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        let len = (seed >> 16) as usize % 200;
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                // mostly sixel data, with the occasional other byte
+                match (seed >> 16) % 100 {
+                    0..=94 => b'?' + (seed >> 8) as u8 % 64,
+                    _ => (seed >> 8) as u8,
+                }
+            })
+            .collect();
+        assert_matches_scalar(&bytes);
+    }
+}
@@ -1,9 +1,23 @@
 use arrayvec::ArrayVec;
+use std::io::{self, Write};
 use std::str;
 
 use crate::ParserError;
 
+/// # Serialization
+/// With the `serde` feature, events use serde's externally tagged representation: variants
+/// without fields are serialized as their name, and other variants as a map from their name to
+/// their fields. In JSON:
+/// ```text
+/// {"ColorIntroducer":{"color_number":1,"color_coordinate_system":{"RGB":[100,0,0]}}}
+/// {"RasterAttribute":{"pan":1,"pad":1,"ph":10,"pv":null}}
+/// {"Data":{"byte":126}}
+/// {"UnknownSequence":[27,80,null,null,null]}
+/// "End"
+/// ```
+/// Variant and field names are part of this representation and are kept stable.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SixelEvent {
     ColorIntroducer {
         color_number: u16,
@@ -31,6 +45,9 @@ pub enum SixelEvent {
     GotoNextLine,
     UnknownSequence([Option<u8>; 5]),
     End,
+    /// The input ended inside an image, before its string terminator. Emitted by
+    /// [`Parser::finish`](crate::Parser::finish) in place of `End`.
+    Unterminated,
 }
 
 impl SixelEvent {
@@ -76,7 +93,7 @@ impl SixelEvent {
         }
     }
     pub fn color_introducer_from_fields(
-        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 5>,
+        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 8>,
     ) -> Result<SixelEvent, ParserError> {
         let mut byte_fields = pending_event_fields.drain(..);
         let color_number = mandatory_field_u16(byte_fields.next())?;
@@ -84,6 +101,9 @@ impl SixelEvent {
         let x = optional_usize_field(byte_fields.next())?;
         let y = optional_usize_field(byte_fields.next())?;
         let z = optional_usize_field(byte_fields.next())?;
+        if byte_fields.any(|field| !field.is_empty()) {
+            return Err(ParserError::ParsingError);
+        }
         match (coordinate_system_indicator, x, y, z) {
             (Some(coordinate_system_indicator), Some(x), Some(y), Some(z)) => {
                 let event = SixelEvent::ColorIntroducer {
@@ -117,27 +137,27 @@ impl SixelEvent {
         Ok(event)
     }
     pub fn raster_attribute_from_fields(
-        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 5>,
+        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 8>,
     ) -> Result<SixelEvent, ParserError> {
         let mut byte_fields = pending_event_fields.drain(..);
         let pan = mandatory_usize_field(byte_fields.next())?;
         let pad = mandatory_usize_field(byte_fields.next())?;
         let ph = optional_usize_field(byte_fields.next())?;
         let pv = optional_usize_field(byte_fields.next())?;
-        if byte_fields.next().is_some() {
+        if byte_fields.any(|field| !field.is_empty()) {
             return Err(ParserError::ParsingError);
         }
         let event = SixelEvent::RasterAttribute { pan, pad, ph, pv };
         Ok(event)
     }
     pub fn dcs_from_fields(
-        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 5>,
+        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 8>,
     ) -> Result<SixelEvent, ParserError> {
         let mut byte_fields = pending_event_fields.drain(..);
         let macro_parameter = optional_field(byte_fields.next())?;
         let transparent_background = optional_field(byte_fields.next())?;
         let horizontal_pixel_distance = optional_usize_field(byte_fields.next())?;
-        if byte_fields.next().is_some() {
+        if byte_fields.any(|field| !field.is_empty()) {
             return Err(ParserError::ParsingError);
         }
         let event = SixelEvent::Dcs {
@@ -155,12 +175,12 @@ impl SixelEvent {
         Ok(event)
     }
     pub fn repeat_from_fields(
-        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 5>,
+        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 8>,
         byte_to_repeat: u8,
     ) -> Result<SixelEvent, ParserError> {
         let mut byte_fields = pending_event_fields.drain(..);
         let repeat_count = mandatory_usize_field(byte_fields.next())?;
-        if byte_fields.next().is_some() {
+        if byte_fields.any(|field| !field.is_empty()) {
             return Err(ParserError::ParsingError);
         }
         let event = SixelEvent::Repeat {
@@ -169,9 +189,112 @@ impl SixelEvent {
         };
         Ok(event)
     }
+    /// Writes the serialized sixel bytes of this event. Feeding them back to a
+    /// [`Parser`](crate::Parser) produces the same event: an omitted parameter that is followed by
+    /// a given one is written as an empty field.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system,
+            } => {
+                write!(writer, "#{}", color_number)?;
+                match color_coordinate_system {
+                    Some(ColorCoordinateSystem::HLS(x, y, z)) => {
+                        write!(writer, ";1;{};{};{}", x, y, z)?
+                    }
+                    Some(ColorCoordinateSystem::RGB(x, y, z)) => {
+                        write!(writer, ";2;{};{};{}", x, y, z)?
+                    }
+                    None => {}
+                }
+            }
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                write!(writer, "\"{};{}", pan, pad)?;
+                match (ph, pv) {
+                    (Some(ph), Some(pv)) => write!(writer, ";{};{}", ph, pv)?,
+                    (Some(ph), None) => write!(writer, ";{}", ph)?,
+                    (None, Some(pv)) => write!(writer, ";;{}", pv)?,
+                    (None, None) => {}
+                }
+            }
+            SixelEvent::Data { byte } => writer.write_all(&[*byte])?,
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => {
+                write!(writer, "!{}", repeat_count)?;
+                writer.write_all(&[*byte_to_repeat])?;
+            }
+            SixelEvent::Dcs {
+                macro_parameter,
+                transparent_background,
+                horizontal_pixel_distance,
+            } => {
+                writer.write_all(b"\x1bP")?;
+                let fields = [
+                    macro_parameter.map(usize::from),
+                    transparent_background.map(usize::from),
+                    *horizontal_pixel_distance,
+                ];
+                let field_count = fields
+                    .iter()
+                    .rposition(|f| f.is_some())
+                    .map_or(0, |i| i + 1);
+                for (i, field) in fields[..field_count].iter().enumerate() {
+                    if i > 0 {
+                        writer.write_all(b";")?;
+                    }
+                    if let Some(field) = field {
+                        write!(writer, "{}", field)?;
+                    }
+                }
+                writer.write_all(b"q")?;
+            }
+            SixelEvent::GotoBeginningOfLine => writer.write_all(b"$")?,
+            SixelEvent::GotoNextLine => writer.write_all(b"-")?,
+            SixelEvent::UnknownSequence(bytes) => {
+                for byte in bytes.iter().flatten() {
+                    writer.write_all(&[*byte])?;
+                }
+            }
+            SixelEvent::End => writer.write_all(b"\x1b\\")?,
+            SixelEvent::Unterminated => {}
+        }
+        Ok(())
+    }
+    /// The number of bytes [`SixelEvent::write_to`] would write for this event.
+    pub fn serialized_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        // writing to a ByteCounter cannot fail
+        let _ = self.write_to(&mut counter);
+        counter.0
+    }
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An event emitted by [`Parser::advance_slice`](crate::Parser::advance_slice).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliceEvent<'a> {
+    /// Consecutive sixel data bytes (`?` to `~`), borrowed from the input. Equivalent to one
+    /// `SixelEvent::Data` event per byte.
+    DataRun(&'a [u8]),
+    Event(SixelEvent),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorCoordinateSystem {
     HLS(usize, usize, usize),
     RGB(usize, usize, usize),
@@ -214,20 +337,22 @@ fn mandatory_usize_field(field: Option<ArrayVec<u8, 5>>) -> Result<usize, Parser
 
 fn optional_field(field: Option<ArrayVec<u8, 5>>) -> Result<Option<u8>, ParserError> {
     match field {
-        Some(field) => {
+        Some(field) if !field.is_empty() => {
             let parsed = bytes_to_u8(field)?;
             Ok(Some(parsed))
         }
-        None => Ok(None),
+        _ => Ok(None),
     }
 }
 
-fn optional_usize_field(field: Option<ArrayVec<u8, 5>>) -> Result<Option<usize>, ParserError> {
+pub(crate) fn optional_usize_field(
+    field: Option<ArrayVec<u8, 5>>,
+) -> Result<Option<usize>, ParserError> {
     match field {
-        Some(field) => {
+        Some(field) if !field.is_empty() => {
             let parsed = bytes_to_usize(field)?;
             Ok(Some(parsed))
         }
-        None => Ok(None),
+        _ => Ok(None),
     }
 }
@@ -0,0 +1,188 @@
+//! A tokenizer for DECDLD soft fonts (dynamically redefinable character sets).
+//!
+//! A soft font is sent as `ESC P Pfn;Pcn;Pe;Pcmw;Pss;Pt;Pcmh;Pcss { Dscs Sxbp1;Sxbp2;... ESC \`.
+//! Each glyph bitmap (`Sxbp`) is sixel data: every data byte is a column of 6 pixels, glyph rows
+//! are separated by `/` and glyphs by `;`. Glyphs are loaded into consecutive characters, starting
+//! at `Pcn`.
+//!
+//! Soft fonts are a mode of the [`Parser`]: [`Parser::advance_with_soft_fonts`] reports the
+//! introducer as a [`SoftFontEvent::Dld`] event and the bitmaps as `Data`, `NextRow` and
+//! `NextGlyph` events, using the same parameter and sixel data handling as for sixel images.
+//! [`Parser::advance`] skips soft fonts. [`SoftFontParser`] is a parser that only reports soft
+//! fonts, bytes outside of DECDLD strings (including sixel images) are ignored.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::{SoftFontEvent, SoftFontParser};
+//!
+//! let mut events = vec![];
+//! let mut parser = SoftFontParser::new();
+//! for byte in b"\x1bP1;1;1;5;0;2;12;0{ @~~/BB;??\x1b\\" {
+//!     parser.advance(byte, |event| events.push(event));
+//! }
+//! let SoftFontEvent::Dld { starting_character, charset, .. } = events[0] else {
+//!     panic!("not a soft font");
+//! };
+//! assert_eq!(starting_character, Some(1));
+//! assert_eq!(charset, [Some(b' '), Some(b'@'), None]);
+//! use SoftFontEvent::{Data, End, NextGlyph, NextRow};
+//! let glyphs = [
+//!     Data { byte: b'~' }, Data { byte: b'~' }, NextRow, Data { byte: b'B' }, Data { byte: b'B' },
+//!     NextGlyph, Data { byte: b'?' }, Data { byte: b'?' }, End,
+//! ];
+//! assert_eq!(events[1..], glyphs);
+//! ```
+
+use arrayvec::ArrayVec;
+
+use crate::sixel_event::optional_usize_field;
+use crate::{is_sixel_data, Parser, ParserError, ParserState, SixelEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoftFontEvent {
+    /// The introducer of a soft font. Omitted parameters are `None`; their meaning (and that of
+    /// 0) depends on the terminal.
+    Dld {
+        /// `Pfn`, the font buffer to load.
+        font_number: Option<usize>,
+        /// `Pcn`, the character that the first glyph replaces.
+        starting_character: Option<usize>,
+        /// `Pe`, which existing glyphs to erase.
+        erase_control: Option<usize>,
+        /// `Pcmw`, the width of each glyph in pixels (or a legacy size code).
+        character_matrix_width: Option<usize>,
+        /// `Pss`, the screen size (columns and lines) the font is for.
+        font_set_size: Option<usize>,
+        /// `Pt`, whether this is a text font or a full-cell font.
+        text_or_full_cell: Option<usize>,
+        /// `Pcmh`, the height of each glyph in pixels.
+        character_matrix_height: Option<usize>,
+        /// `Pcss`, whether the character set has 94 or 96 characters.
+        character_set_size: Option<usize>,
+        /// `Dscs`, the up to two intermediate bytes and the final byte that designate the font.
+        charset: [Option<u8>; 3],
+    },
+    /// A sixel column of the current row of the current glyph.
+    Data {
+        byte: u8,
+    },
+    /// `/`, the next six rows of pixels of the current glyph start.
+    NextRow,
+    /// `;`, the next glyph starts.
+    NextGlyph,
+    End,
+    /// The input ended inside a soft font, before its string terminator. Emitted by
+    /// [`Parser::finish_with_soft_fonts`] in place of `End`.
+    Unterminated,
+}
+
+impl SoftFontEvent {
+    pub(crate) fn dld_from_fields(
+        pending_event_fields: &mut ArrayVec<ArrayVec<u8, 5>, 8>,
+        charset_bytes: &[u8],
+    ) -> Result<SoftFontEvent, ParserError> {
+        let mut byte_fields = pending_event_fields.drain(..);
+        let mut charset = [None; 3];
+        for (byte, charset_byte) in charset_bytes.iter().zip(charset.iter_mut()) {
+            *charset_byte = Some(*byte);
+        }
+        Ok(SoftFontEvent::Dld {
+            font_number: optional_usize_field(byte_fields.next())?,
+            starting_character: optional_usize_field(byte_fields.next())?,
+            erase_control: optional_usize_field(byte_fields.next())?,
+            character_matrix_width: optional_usize_field(byte_fields.next())?,
+            font_set_size: optional_usize_field(byte_fields.next())?,
+            text_or_full_cell: optional_usize_field(byte_fields.next())?,
+            character_matrix_height: optional_usize_field(byte_fields.next())?,
+            character_set_size: optional_usize_field(byte_fields.next())?,
+            charset,
+        })
+    }
+}
+
+impl Parser {
+    // handles the bytes of a soft font after the `{` of its introducer, returns whether the byte
+    // was consumed
+    pub(crate) fn advance_soft_font(
+        &mut self,
+        byte: u8,
+        cb: &mut impl FnMut(SixelEvent),
+        font_cb: &mut impl FnMut(SoftFontEvent),
+    ) -> bool {
+        match (self.state, byte) {
+            (ParserState::SoftFontCharset, 0x20..=0x2f) if self.charset.len() < 2 => {
+                self.charset.push(byte);
+                let _ = self.raw_instruction.try_push(byte);
+            }
+            (ParserState::SoftFontCharset, 0x30..=0x7e) => {
+                self.charset.push(byte);
+                match SoftFontEvent::dld_from_fields(&mut self.pending_event_fields, &self.charset)
+                {
+                    Ok(event) => {
+                        self.raw_instruction.clear();
+                        self.charset.clear();
+                        self.in_soft_font = true;
+                        self.state = ParserState::SoftFont;
+                        font_cb(event);
+                    }
+                    Err(e) => self.handle_error(e, Some(byte), cb),
+                }
+            }
+            (ParserState::SoftFontCharset, _) => {
+                // not a soft font, the byte is handled as it would be after any other instruction
+                self.charset.clear();
+                self.handle_error(ParserError::ParsingError, None, cb);
+                return false;
+            }
+            (ParserState::SoftFont, 27) => {
+                self.raw_instruction.clear();
+                let _ = self.raw_instruction.try_push(byte);
+                self.state = ParserState::EscapeCharacter;
+            }
+            (ParserState::SoftFont, _) => match byte {
+                b'/' => font_cb(SoftFontEvent::NextRow),
+                b';' => font_cb(SoftFontEvent::NextGlyph),
+                _ if is_sixel_data(byte) => font_cb(SoftFontEvent::Data { byte }),
+                // whitespace and other bytes are ignored
+                _ => {}
+            },
+            (ParserState::EscapeCharacter, _) if self.in_soft_font => {
+                font_cb(SoftFontEvent::End);
+                self.in_soft_font = false;
+                if byte != b'\\' {
+                    // any other escape sequence cancels the string and is handled as usual
+                    return false;
+                }
+                self.clear();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A [`Parser`] that only reports soft fonts.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoftFontParser {
+    parser: Parser,
+}
+
+impl SoftFontParser {
+    pub fn new() -> Self {
+        SoftFontParser::default()
+    }
+    pub fn advance(&mut self, byte: &u8, cb: impl FnMut(SoftFontEvent)) {
+        self.parser.advance_with_soft_fonts(byte, |_| {}, cb);
+    }
+    /// Signals the end of the input and resets the parser. A soft font that was not ended by its
+    /// string terminator (`ESC \`) is ended with `Unterminated`.
+    pub fn finish(&mut self, cb: impl FnMut(SoftFontEvent)) {
+        self.parser.finish_with_soft_fonts(|_| {}, cb);
+    }
+}
+
+#[cfg(test)]
+#[path = "./soft_font_tests.rs"]
+mod tests;
@@ -0,0 +1,187 @@
+use super::*;
+
+use SoftFontEvent::{Data, End, NextGlyph, NextRow, Unterminated};
+
+fn parse(input: &[u8]) -> Vec<SoftFontEvent> {
+    let mut events = vec![];
+    let mut parser = SoftFontParser::new();
+    for byte in input {
+        parser.advance(byte, |event| events.push(event));
+    }
+    parser.finish(|event| events.push(event));
+    events
+}
+
+fn data(bytes: &[u8]) -> impl Iterator<Item = SoftFontEvent> + '_ {
+    bytes.iter().map(|byte| Data { byte: *byte })
+}
+
+#[test]
+fn dld_parameters() {
+    let events = parse(b"\x1bP0;33;2;8;1;0;16;1{ @\x1b\\");
+    assert_eq!(
+        events,
+        vec![
+            SoftFontEvent::Dld {
+                font_number: Some(0),
+                starting_character: Some(33),
+                erase_control: Some(2),
+                character_matrix_width: Some(8),
+                font_set_size: Some(1),
+                text_or_full_cell: Some(0),
+                character_matrix_height: Some(16),
+                character_set_size: Some(1),
+                charset: [Some(b' '), Some(b'@'), None],
+            },
+            End,
+        ]
+    );
+}
+
+#[test]
+fn omitted_parameters() {
+    let events = parse(b"\x1bP;2;;;;;;9{B\x1b\\");
+    assert_eq!(
+        events[0],
+        SoftFontEvent::Dld {
+            font_number: None,
+            starting_character: Some(2),
+            erase_control: None,
+            character_matrix_width: None,
+            font_set_size: None,
+            text_or_full_cell: None,
+            character_matrix_height: None,
+            character_set_size: Some(9),
+            charset: [Some(b'B'), None, None],
+        }
+    );
+}
+
+#[test]
+fn extra_parameters_are_not_a_soft_font() {
+    assert_eq!(parse(b"\x1bP;2;;;;;;;9;9{B~\x1b\\"), vec![]);
+}
+
+#[test]
+fn glyph_rows_and_glyphs() {
+    let events = parse(b"\x1bP1;1{ @??~~/NN\n;\n@@/BB;;A\x1b\\");
+    let mut expected = vec![];
+    expected.extend(data(b"??~~"));
+    expected.push(NextRow);
+    expected.extend(data(b"NN"));
+    expected.push(NextGlyph);
+    expected.extend(data(b"@@"));
+    expected.push(NextRow);
+    expected.extend(data(b"BB"));
+    expected.extend([NextGlyph, NextGlyph]);
+    expected.extend(data(b"A"));
+    expected.push(End);
+    assert_eq!(events[1..], expected);
+}
+
+#[test]
+fn other_device_control_strings_are_ignored() {
+    let events = parse(b"\x1bPq#1~~-\x1b\\~~\x1bP1$r\x1b\\\x1bP{0~\x1b\\");
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], SoftFontEvent::Dld { .. }));
+    assert_eq!(events[1..], [Data { byte: b'~' }, End]);
+}
+
+#[test]
+fn escape_sequence_cancels_the_font() {
+    let events = parse(b"\x1bP{0~\x1b[0m\x1bP{1@\x1b\\");
+    assert_eq!(events[1..3], [Data { byte: b'~' }, End]);
+    assert!(matches!(
+        events[3],
+        SoftFontEvent::Dld {
+            charset: [Some(b'1'), None, None],
+            ..
+        }
+    ));
+    assert_eq!(events[4..], [Data { byte: b'@' }, End]);
+}
+
+#[test]
+fn unterminated_font() {
+    let events = parse(b"\x1bP{0~/");
+    assert_eq!(events[1..], [Data { byte: b'~' }, NextRow, Unterminated]);
+    assert_eq!(parse(b"\x1bP1;2"), vec![]);
+}
+
+#[test]
+fn parameters_have_at_most_five_digits() {
+    let events = parse(b"\x1bP99999{0\x1b\\");
+    assert!(matches!(
+        events[0],
+        SoftFontEvent::Dld {
+            font_number: Some(99999),
+            ..
+        }
+    ));
+    assert_eq!(parse(b"\x1bP999999{0~\x1b\\"), vec![]);
+}
+
+#[test]
+fn parser_skips_soft_fonts() {
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in b"\x1bP1;1{ @~~/BB;@\x1b\\\x1bPq~\x1b\\" {
+        parser.advance(byte, |event| events.push(event));
+    }
+    assert_eq!(
+        events,
+        vec![
+            SixelEvent::new_dcs(None, None, None),
+            SixelEvent::Data { byte: b'~' },
+            SixelEvent::End,
+        ]
+    );
+}
+
+#[test]
+fn parser_reports_soft_fonts_and_images() {
+    let mut events = vec![];
+    let mut font_events = vec![];
+    let mut parser = Parser::new();
+    for byte in b"\x1bPq~\x1b\\\x1bP{0@/A\x1b\\\x1bPq#1~" {
+        parser.advance_with_soft_fonts(
+            byte,
+            |event| events.push(event),
+            |event| font_events.push(event),
+        );
+    }
+    parser.finish_with_soft_fonts(|event| events.push(event), |event| font_events.push(event));
+    assert_eq!(events.len(), 7);
+    assert_eq!(events[6], SixelEvent::Unterminated);
+    assert!(matches!(font_events[0], SoftFontEvent::Dld { .. }));
+    assert_eq!(
+        font_events[1..],
+        [Data { byte: b'@' }, NextRow, Data { byte: b'A' }, End]
+    );
+}
+
+#[test]
+fn charset_with_two_intermediates() {
+    let events = parse(b"\x1bP{ !@~\x1b\\");
+    assert!(matches!(
+        events[0],
+        SoftFontEvent::Dld {
+            charset: [Some(b' '), Some(b'!'), Some(b'@')],
+            ..
+        }
+    ));
+    assert_eq!(events[1..], [Data { byte: b'~' }, End]);
+}
+
+#[test]
+fn charset_with_three_intermediates_is_not_a_soft_font() {
+    let events = parse(b"\x1bP{ !\"@~\x1b\\\x1bP{0~\x1b\\");
+    assert!(matches!(
+        events[0],
+        SoftFontEvent::Dld {
+            charset: [Some(b'0'), None, None],
+            ..
+        }
+    ));
+    assert_eq!(events[1..], [Data { byte: b'~' }, End]);
+}
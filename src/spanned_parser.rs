@@ -0,0 +1,87 @@
+use std::ops::Range;
+
+use crate::{Parser, ParserState, SixelEvent, SoftFontEvent};
+
+/// A [`Parser`] that also reports the byte span in its input that produced each event.
+///
+/// Spans are offsets from the first byte given to this parser. Whitespace between instructions is
+/// not included in the spans, whitespace inside an instruction is. Soft fonts are skipped, as
+/// with [`Parser::advance`], and are not included in the spans either.
+#[derive(Clone, Debug, Default)]
+pub struct SpannedParser {
+    parser: Parser,
+    offset: usize,
+    token_start: usize,
+    token_end: usize,
+}
+
+impl SpannedParser {
+    pub fn new() -> Self {
+        SpannedParser::default()
+    }
+    pub fn advance(&mut self, byte: &u8, mut cb: impl FnMut(SixelEvent, Range<usize>)) {
+        let offset = self.offset;
+        self.offset += 1;
+        let is_whitespace = byte == &b' ' || byte == &b'\n' || byte == &b'\t';
+        if is_whitespace && self.token_start == offset {
+            self.token_start = offset + 1;
+        }
+        let token_end = self.token_end;
+        let token_start = &mut self.token_start;
+        let mut soft_font_ended = false;
+        let mut cb = |event| {
+            let (span, next_token_start) = match event {
+                // these are only emitted once the byte after them arrives
+                SixelEvent::ColorIntroducer { .. } | SixelEvent::RasterAttribute { .. } => {
+                    (*token_start..std::cmp::max(*token_start, token_end), offset)
+                }
+                SixelEvent::UnknownSequence(bytes) => {
+                    let len = bytes.iter().flatten().count();
+                    let end = std::cmp::min(*token_start + len, offset + 1);
+                    (*token_start..end, end)
+                }
+                _ => (*token_start..offset + 1, offset + 1),
+            };
+            *token_start = next_token_start;
+            cb(event, span);
+        };
+        self.parser.advance_with_soft_fonts(byte, &mut cb, |event| {
+            soft_font_ended |= event == SoftFontEvent::End;
+        });
+        if self.parser.state == ParserState::SoftFont || (soft_font_ended && byte == &b'\\') {
+            self.token_start = offset + 1;
+        } else if self.parser.state == ParserState::EscapeCharacter && self.parser.in_soft_font {
+            // the escape sequence that ends a soft font might start the next token
+            self.token_start = offset;
+        }
+        if !is_whitespace {
+            self.token_end = offset + 1;
+        }
+    }
+    /// Signals the end of the input, see [`Parser::finish`]. Events that are not produced by any
+    /// input bytes (eg. `Unterminated`) have an empty span at the end of the input.
+    pub fn finish(&mut self, mut cb: impl FnMut(SixelEvent, Range<usize>)) {
+        let offset = self.offset;
+        let token_end = self.token_end;
+        let token_start = &mut self.token_start;
+        self.parser.finish(|event| {
+            let span = match event {
+                SixelEvent::ColorIntroducer { .. } | SixelEvent::RasterAttribute { .. } => {
+                    *token_start..std::cmp::max(*token_start, token_end)
+                }
+                SixelEvent::UnknownSequence(bytes) => {
+                    let len = bytes.iter().flatten().count();
+                    *token_start..std::cmp::min(*token_start + len, offset)
+                }
+                _ => offset..offset,
+            };
+            *token_start = span.end;
+            cb(event, span);
+        });
+        self.token_start = offset;
+    }
+    /// The offset of the next byte this parser will receive.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::AddAssign;
+
+use crate::SixelEvent;
+
+const COLOR_REGISTERS: usize = u16::MAX as usize + 1;
+
+/// Collects statistics about one or more [`SixelEvent`] streams in constant memory.
+///
+/// Stats of separate streams can be combined with [`Stats::merge`] (or `+=`).
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// The number of images (`Dcs` events) seen.
+    pub images: usize,
+    /// The serialized size of all events seen.
+    pub bytes: usize,
+    /// The number of bands (six pixel rows) that contain sixel data.
+    pub bands: usize,
+    /// The number of distinct color registers defined, summed over all images.
+    pub colors_defined: usize,
+    /// The number of distinct color registers used for painting, summed over all images.
+    pub colors_used: usize,
+    /// The number of sixel columns described by `Data` and `Repeat` events.
+    pub sixel_columns: usize,
+    /// The serialized size of all `Data` and `Repeat` events.
+    pub data_bytes: usize,
+    /// The number of runs of identical sixel columns.
+    pub runs: usize,
+    pub unknown_sequences: usize,
+    /// The number of pixels painted, a pixel painted in several color passes is counted each time.
+    pub painted_pixels: usize,
+    image: ImageState,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ImageState {
+    defined_colors: RegisterSet,
+    used_colors: RegisterSet,
+    current_color: Option<u16>,
+    band_has_data: bool,
+    last_byte: Option<u8>,
+}
+
+#[derive(Clone, Debug)]
+struct RegisterSet(Box<[u64; COLOR_REGISTERS / 64]>);
+
+impl Default for RegisterSet {
+    fn default() -> Self {
+        RegisterSet(Box::new([0; COLOR_REGISTERS / 64]))
+    }
+}
+
+impl RegisterSet {
+    // returns true if the register was not in the set before
+    pub fn insert(&mut self, register: u16) -> bool {
+        let (word, bit) = (register as usize / 64, register as usize % 64);
+        let is_new = self.0[word] & (1 << bit) == 0;
+        self.0[word] |= 1 << bit;
+        is_new
+    }
+    pub fn clear(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+    pub fn advance(&mut self, event: SixelEvent) {
+        self.bytes += event.serialized_len();
+        match event {
+            SixelEvent::Dcs { .. } => {
+                self.images += 1;
+                self.image.defined_colors.clear();
+                self.image.used_colors.clear();
+                self.image.current_color = None;
+                self.image.band_has_data = false;
+                self.image.last_byte = None;
+            }
+            SixelEvent::ColorIntroducer {
+                color_number,
+                color_coordinate_system,
+            } => {
+                if color_coordinate_system.is_some()
+                    && self.image.defined_colors.insert(color_number)
+                {
+                    self.colors_defined += 1;
+                }
+                self.image.current_color = Some(color_number);
+                self.image.last_byte = None;
+            }
+            SixelEvent::Data { byte } => self.count_data(byte, 1, event.serialized_len()),
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => self.count_data(byte_to_repeat, repeat_count, event.serialized_len()),
+            SixelEvent::GotoBeginningOfLine => self.image.last_byte = None,
+            SixelEvent::GotoNextLine => {
+                self.image.band_has_data = false;
+                self.image.last_byte = None;
+            }
+            SixelEvent::UnknownSequence(_) => self.unknown_sequences += 1,
+            SixelEvent::RasterAttribute { .. } | SixelEvent::End | SixelEvent::Unterminated => {}
+        }
+    }
+    /// Adds the counts of another stream to these stats.
+    pub fn merge(&mut self, other: &Stats) {
+        self.images += other.images;
+        self.bytes += other.bytes;
+        self.bands += other.bands;
+        self.colors_defined += other.colors_defined;
+        self.colors_used += other.colors_used;
+        self.sixel_columns += other.sixel_columns;
+        self.data_bytes += other.data_bytes;
+        self.runs += other.runs;
+        self.unknown_sequences += other.unknown_sequences;
+        self.painted_pixels += other.painted_pixels;
+    }
+    /// The number of sixel columns per byte of sixel data, 1.0 means nothing was compressed.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.data_bytes == 0 {
+            return 1.0;
+        }
+        self.sixel_columns as f64 / self.data_bytes as f64
+    }
+    /// The average number of identical sixel columns in a row.
+    pub fn average_run_length(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.sixel_columns as f64 / self.runs as f64
+    }
+    fn count_data(&mut self, byte: u8, count: usize, serialized_len: usize) {
+        self.sixel_columns += count;
+        self.data_bytes += serialized_len;
+        if self.image.last_byte != Some(byte) {
+            self.runs += 1;
+            self.image.last_byte = Some(byte);
+        }
+        if !self.image.band_has_data {
+            self.bands += 1;
+            self.image.band_has_data = true;
+        }
+        let pixels = byte.wrapping_sub(b'?').count_ones() as usize;
+        if pixels > 0 {
+            self.painted_pixels += pixels * count;
+            if let Some(color) = self.image.current_color {
+                if self.image.used_colors.insert(color) {
+                    self.colors_used += 1;
+                }
+            }
+        }
+    }
+}
+
+impl AddAssign<&Stats> for Stats {
+    fn add_assign(&mut self, other: &Stats) {
+        self.merge(other);
+    }
+}
+
+#[cfg(test)]
+#[path = "./stats_tests.rs"]
+mod tests;
@@ -0,0 +1,91 @@
+use crate::{Parser, Stats};
+
+fn collect_stats(sample: &str) -> Stats {
+    let mut stats = Stats::new();
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(byte, |sixel_event| stats.advance(sixel_event));
+    }
+    stats
+}
+
+#[test]
+fn basic_sample_stats() {
+    let sample = "
+        \u{1b}Pq
+        \"2;1;100;200
+        #0;2;0;0;0#1;2;100;100;0#2;2;0;100;0
+        #1~~@@vv@@~~@@~~$
+        #2??}}GG}}??}}??-
+        #1!14@
+        \u{1b}\\
+    ";
+    let stats = collect_stats(sample);
+    assert_eq!(stats.images, 1);
+    assert_eq!(
+        stats.bytes,
+        sample.split_whitespace().map(str::len).sum::<usize>()
+    );
+    assert_eq!(stats.bands, 2);
+    assert_eq!(stats.colors_defined, 3);
+    assert_eq!(stats.colors_used, 2);
+    assert_eq!(stats.sixel_columns, 42);
+    assert_eq!(stats.data_bytes, 32);
+    assert_eq!(stats.runs, 15);
+    assert_eq!(stats.unknown_sequences, 0);
+    // ~ paints 6 pixels, } and v paint 5, G and @ paint 1
+    assert_eq!(stats.painted_pixels, 6 * 6 + 5 * 2 + 6 + 5 * 6 + 2 + 14);
+    assert_eq!(stats.compression_ratio(), 42.0 / 32.0);
+    assert_eq!(stats.average_run_length(), 42.0 / 15.0);
+}
+
+#[test]
+fn colors_are_counted_per_image() {
+    let sample = "
+        \u{1b}Pq#1;2;0;0;0#1;2;0;0;0#1~#2~\u{1b}\\
+        \u{1b}Pq#1;2;0;0;0#1~\u{1b}\\
+    ";
+    let stats = collect_stats(sample);
+    assert_eq!(stats.images, 2);
+    assert_eq!(stats.colors_defined, 2);
+    assert_eq!(stats.colors_used, 3);
+}
+
+#[test]
+fn blank_data_does_not_use_colors() {
+    let stats = collect_stats("\u{1b}Pq#1!20?\u{1b}\\");
+    assert_eq!(stats.colors_used, 0);
+    assert_eq!(stats.painted_pixels, 0);
+    assert_eq!(stats.sixel_columns, 20);
+    assert_eq!(stats.compression_ratio(), 5.0);
+}
+
+#[test]
+fn unknown_sequences_are_counted() {
+    let stats = collect_stats("\u{1b}P1122q%\u{1b}\\");
+    assert_eq!(stats.unknown_sequences, 3);
+}
+
+#[test]
+fn stats_are_combinable() {
+    let first = collect_stats("\u{1b}Pq#1;2;0;0;0#1!10~\u{1b}\\");
+    let second = collect_stats("\u{1b}Pq#1;2;0;0;0#1~-~\u{1b}\\");
+    let mut combined = Stats::new();
+    combined += &first;
+    combined.merge(&second);
+    assert_eq!(combined.images, 2);
+    assert_eq!(combined.bytes, first.bytes + second.bytes);
+    assert_eq!(combined.bands, 3);
+    assert_eq!(combined.colors_defined, 2);
+    assert_eq!(combined.colors_used, 2);
+    assert_eq!(combined.sixel_columns, 12);
+    assert_eq!(combined.runs, 3);
+    assert_eq!(combined.painted_pixels, 72);
+}
+
+#[test]
+fn empty_stats() {
+    let stats = Stats::new();
+    assert_eq!(stats.compression_ratio(), 1.0);
+    assert_eq!(stats.average_run_length(), 0.0);
+}
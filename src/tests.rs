@@ -1,7 +1,7 @@
 use insta::assert_snapshot;
 use std::str;
 
-use crate::{Parser, SixelEvent};
+use crate::{Parser, ParserState, SixelEvent, SliceEvent, SpannedParser};
 
 #[test]
 fn basic_sample() {
@@ -68,6 +68,23 @@ fn dcs_event_with_partial_optional_fields() {
     assert_eq!(events, expected);
 }
 
+#[test]
+fn omitted_fields_keep_the_position_of_later_fields() {
+    let sample = "\u{1b}P;1q\"1;1;;6\u{1b}\\";
+    let sample_bytes = sample.as_bytes();
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    let expected = vec![
+        SixelEvent::new_dcs(None, Some(1), None),
+        SixelEvent::new_raster(1, 1, None, Some(6)).unwrap(),
+        SixelEvent::End,
+    ];
+    assert_eq!(events, expected);
+}
+
 #[test]
 fn corrupted_dcs_event() {
     let sample = "\u{1b}P1122q\u{1b}\\";
@@ -1020,3 +1037,414 @@ fn beginning_of_line_event_after_next_line_event() {
     ];
     assert_eq!(events, expected);
 }
+
+#[test]
+fn spanned_events() {
+    let sample = "\u{1b}Pq \"1;1;4;6\n#1;2;100;0;0~!3@$-\u{1b}\\";
+    let sample_bytes = sample.as_bytes();
+    let mut events = vec![];
+    let mut parser = SpannedParser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event, span| events.push((sixel_event, span)));
+    }
+    let expected = vec![
+        (SixelEvent::new_dcs(None, None, None), 0..3),
+        (
+            SixelEvent::new_raster(1, 1, Some(4), Some(6)).unwrap(),
+            4..12,
+        ),
+        (
+            SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+            13..25,
+        ),
+        (SixelEvent::Data { byte: b'~' }, 25..26),
+        (SixelEvent::new_repeat(3, b'@').unwrap(), 26..29),
+        (SixelEvent::GotoBeginningOfLine, 29..30),
+        (SixelEvent::GotoNextLine, 30..31),
+        (SixelEvent::End, 31..33),
+    ];
+    assert_eq!(events, expected);
+    assert_eq!(parser.offset(), sample_bytes.len());
+}
+
+#[test]
+fn spanned_unknown_sequences() {
+    let sample = "\u{1b}P1122q\u{1b}\\";
+    let sample_bytes = sample.as_bytes();
+    let mut events = vec![];
+    let mut parser = SpannedParser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event, span| events.push((sixel_event, span)));
+    }
+    let expected = vec![
+        (
+            SixelEvent::UnknownSequence([Some(27), Some(b'P'), Some(b'1'), Some(b'1'), Some(b'2')]),
+            0..5,
+        ),
+        (
+            SixelEvent::UnknownSequence([Some(b'2'), Some(b'q'), None, None, None]),
+            5..7,
+        ),
+        (SixelEvent::End, 7..9),
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn start_in_body() {
+    let sample = "#1;2;100;0;0~~-@";
+    let mut events = vec![];
+    let mut parser = Parser::new_in_body(Some(0), Some(1), None);
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![
+        SixelEvent::new_dcs(Some(0), Some(1), None),
+        SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::GotoNextLine,
+        SixelEvent::Data { byte: b'@' },
+        SixelEvent::End,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn start_in_body_and_finish_immediately() {
+    let mut events = vec![];
+    let mut parser = Parser::new_in_body(None, None, None);
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![SixelEvent::new_dcs(None, None, None), SixelEvent::End];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn finish_emits_pending_color_introducer() {
+    let sample = "\u{1b}Pq#1;2;100;0;0";
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+        SixelEvent::Unterminated,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn finish_emits_pending_raster_attribute() {
+    let sample = "\u{1b}Pq\"1;1;10;10";
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::new_raster(1, 1, Some(10), Some(10)).unwrap(),
+        SixelEvent::Unterminated,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn finish_reports_incomplete_instruction() {
+    let sample = "\u{1b}Pq~!12";
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::UnknownSequence([Some(b'!'), Some(b'1'), Some(b'2'), None, None]),
+        SixelEvent::Unterminated,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn finish_outside_of_image() {
+    let sample = "\u{1b}Pq~\u{1b}\\";
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|sixel_event| events.push(sixel_event));
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::End,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn finish_resets_parser() {
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in "\u{1b}Pq#1".as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    parser.finish(|_| {});
+    for byte in "\u{1b}Pq~\u{1b}\\".as_bytes() {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    let expected = vec![
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::new_dcs(None, None, None),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::End,
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn spanned_events_after_soft_fonts() {
+    let sample = "\u{1b}P1;1{ @~~/BB\u{1b}\\\u{1b}Pq~\u{1b}P{0~\u{1b}P1122q";
+    let mut events = vec![];
+    let mut parser = SpannedParser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event, span| events.push((sixel_event, span)));
+    }
+    let expected = vec![
+        (SixelEvent::new_dcs(None, None, None), 15..18),
+        (SixelEvent::Data { byte: b'~' }, 18..19),
+        (
+            SixelEvent::UnknownSequence([Some(27), Some(b'P'), Some(b'1'), Some(b'1'), Some(b'2')]),
+            24..29,
+        ),
+        (
+            SixelEvent::UnknownSequence([Some(b'2'), Some(b'q'), None, None, None]),
+            29..31,
+        ),
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn spanned_finish() {
+    let sample = "\u{1b}Pq~#1;2;100;0;0 ";
+    let mut events = vec![];
+    let mut parser = SpannedParser::new();
+    for byte in sample.as_bytes() {
+        parser.advance(&byte, |sixel_event, span| events.push((sixel_event, span)));
+    }
+    parser.finish(|sixel_event, span| events.push((sixel_event, span)));
+    let expected = vec![
+        (SixelEvent::new_dcs(None, None, None), 0..3),
+        (SixelEvent::Data { byte: b'~' }, 3..4),
+        (
+            SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+            4..16,
+        ),
+        (SixelEvent::Unterminated, 17..17),
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn parser_state() {
+    let mut parser = Parser::new();
+    assert_eq!(parser.state(), ParserState::Ground);
+    for byte in "\u{1b}Pq#1;2".as_bytes() {
+        parser.advance(&byte, |_| {});
+    }
+    assert_eq!(parser.state(), ParserState::ColorIntroducer);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn restore_serialized_parser_at_every_offset() {
+    let sample = "
+        \u{1b}P0;1;0q
+        \"2;1;100;200
+        #0;2;0;0;0#1;2;100;100;0#2;2;0;100;0
+        #1~~@@vv@@~~@@~~$
+        #2??}}GG}}??}}??-
+        #1!14@ab\u{1b}xy
+        \u{1b}\\
+    ";
+    let sample_bytes = sample.as_bytes();
+    let mut expected = vec![];
+    let mut parser = Parser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event| expected.push(sixel_event));
+    }
+    for split in 0..sample_bytes.len() {
+        let mut events = vec![];
+        let mut parser = Parser::new();
+        for byte in &sample_bytes[..split] {
+            parser.advance(&byte, |sixel_event| events.push(sixel_event));
+        }
+        let snapshot = serde_json::to_string(&parser).unwrap();
+        let mut parser: Parser = serde_json::from_str(&snapshot).unwrap();
+        for byte in &sample_bytes[split..] {
+            parser.advance(&byte, |sixel_event| events.push(sixel_event));
+        }
+        assert_eq!(events, expected, "snapshot taken after {} bytes", split);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_events_to_json() {
+    let events = [
+        (
+            SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+            r#"{"ColorIntroducer":{"color_number":1,"color_coordinate_system":{"RGB":[100,0,0]}}}"#,
+        ),
+        (
+            SixelEvent::new_color_introducer(2, Some(1), Some(120), Some(50), Some(100)).unwrap(),
+            r#"{"ColorIntroducer":{"color_number":2,"color_coordinate_system":{"HLS":[120,50,100]}}}"#,
+        ),
+        (
+            SixelEvent::new_color_introducer(3, None, None, None, None).unwrap(),
+            r#"{"ColorIntroducer":{"color_number":3,"color_coordinate_system":null}}"#,
+        ),
+        (
+            SixelEvent::new_raster(1, 1, Some(10), None).unwrap(),
+            r#"{"RasterAttribute":{"pan":1,"pad":1,"ph":10,"pv":null}}"#,
+        ),
+        (SixelEvent::Data { byte: b'~' }, r#"{"Data":{"byte":126}}"#),
+        (
+            SixelEvent::new_repeat(14, b'@').unwrap(),
+            r#"{"Repeat":{"repeat_count":14,"byte_to_repeat":64}}"#,
+        ),
+        (
+            SixelEvent::new_dcs(Some(0), Some(1), None),
+            r#"{"Dcs":{"macro_parameter":0,"transparent_background":1,"horizontal_pixel_distance":null}}"#,
+        ),
+        (SixelEvent::GotoBeginningOfLine, r#""GotoBeginningOfLine""#),
+        (SixelEvent::GotoNextLine, r#""GotoNextLine""#),
+        (
+            SixelEvent::UnknownSequence([Some(27), Some(b'P'), None, None, None]),
+            r#"{"UnknownSequence":[27,80,null,null,null]}"#,
+        ),
+        (SixelEvent::End, r#""End""#),
+        (SixelEvent::Unterminated, r#""Unterminated""#),
+    ];
+    for (event, json) in events {
+        assert_eq!(serde_json::to_string(&event).unwrap(), json);
+        assert_eq!(serde_json::from_str::<SixelEvent>(json).unwrap(), event);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_parser_states_to_json() {
+    let states = [
+        (ParserState::Ground, r#""Ground""#),
+        (ParserState::DeviceControlString, r#""DeviceControlString""#),
+        (ParserState::EscapeCharacter, r#""EscapeCharacter""#),
+        (ParserState::ColorIntroducer, r#""ColorIntroducer""#),
+        (ParserState::RasterAttribute, r#""RasterAttribute""#),
+        (ParserState::GraphicsRepeatIntroducer, r#""GraphicsRepeatIntroducer""#),
+        (ParserState::UnknownSequence, r#""UnknownSequence""#),
+        (ParserState::SoftFontCharset, r#""SoftFontCharset""#),
+        (ParserState::SoftFont, r#""SoftFont""#),
+    ];
+    for (state, json) in states {
+        assert_eq!(serde_json::to_string(&state).unwrap(), json);
+        assert_eq!(serde_json::from_str::<ParserState>(json).unwrap(), state);
+    }
+}
+
+#[test]
+fn data_runs() {
+    let sample = "\u{1b}Pq#1~~@@vv$#2??}} }}-!3~a\u{1b}\\";
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    parser.advance_slice(sample.as_bytes(), |slice_event| events.push(slice_event));
+    let expected = vec![
+        SliceEvent::Event(SixelEvent::new_dcs(None, None, None)),
+        SliceEvent::Event(SixelEvent::new_color_introducer(1, None, None, None, None).unwrap()),
+        SliceEvent::DataRun(b"~~@@vv"),
+        SliceEvent::Event(SixelEvent::GotoBeginningOfLine),
+        SliceEvent::Event(SixelEvent::new_color_introducer(2, None, None, None, None).unwrap()),
+        SliceEvent::DataRun(b"??}}"),
+        SliceEvent::DataRun(b"}}"),
+        SliceEvent::Event(SixelEvent::GotoNextLine),
+        SliceEvent::Event(SixelEvent::new_repeat(3, b'~').unwrap()),
+        SliceEvent::DataRun(b"a"),
+        SliceEvent::Event(SixelEvent::End),
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn data_runs_match_per_byte_events_at_every_split() {
+    let sample = "
+        \u{1b}P0;1;0q
+        \"2;1;100;200
+        #0;2;0;0;0#1;2;100;100;0#2;2;0;100;0
+        #1~~@@vv@@~~@@~~$
+        #2??}}GG}}??}}??-
+        #1!14@ab\u{1b}xy
+        \u{1b}\\
+    ";
+    let sample_bytes = sample.as_bytes();
+    let mut expected = vec![];
+    let mut parser = Parser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event| expected.push(sixel_event));
+    }
+    for split in 0..sample_bytes.len() {
+        let mut events = vec![];
+        let mut parser = Parser::new();
+        for chunk in [&sample_bytes[..split], &sample_bytes[split..]] {
+            parser.advance_slice(chunk, |slice_event| match slice_event {
+                SliceEvent::DataRun(bytes) => {
+                    events.extend(bytes.iter().map(|byte| SixelEvent::Data { byte: *byte }))
+                }
+                SliceEvent::Event(sixel_event) => events.push(sixel_event),
+            });
+        }
+        assert_eq!(events, expected, "input split after {} bytes", split);
+    }
+}
+
+#[test]
+fn overlong_field_before_separator() {
+    let sample = "#1234567;2~";
+    let sample_bytes = sample.as_bytes();
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    let expected = vec![
+        SixelEvent::UnknownSequence([Some(b'#'), Some(b'1'), Some(b'2'), Some(b'3'), Some(b'4')]),
+        SixelEvent::UnknownSequence([Some(b'5'), Some(b'6'), Some(b'7'), Some(b';'), None]),
+        SixelEvent::UnknownSequence([Some(b'2'), None, None, None, None]),
+        SixelEvent::Data { byte: b'~' },
+    ];
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn unknown_sequence_of_five_bytes_before_rejected_byte() {
+    let sample = "#123456789;";
+    let sample_bytes = sample.as_bytes();
+    let mut events = vec![];
+    let mut parser = Parser::new();
+    for byte in sample_bytes {
+        parser.advance(&byte, |sixel_event| events.push(sixel_event));
+    }
+    let expected = vec![
+        SixelEvent::UnknownSequence([Some(b'#'), Some(b'1'), Some(b'2'), Some(b'3'), Some(b'4')]),
+        SixelEvent::UnknownSequence([Some(b'5'), Some(b'6'), Some(b'7'), Some(b'8'), Some(b'9')]),
+        SixelEvent::UnknownSequence([Some(b';'), None, None, None, None]),
+    ];
+    assert_eq!(events, expected);
+}
@@ -0,0 +1,204 @@
+//! Splits sixel images into tiles aligned to terminal cells.
+//!
+//! Every tile is an independent sixel image: it has the `Dcs` and raster attributes (if any) of
+//! its source image (resized to the tile), only the color definitions it uses, and the sixel data
+//! of the cell it covers. Each row of tiles is cropped out of the source image with a [`Crop`]
+//! once, and then split into its tiles in a single pass over the row's events. Every tile is then
+//! passed through an [`Optimizer`].
+
+use std::io;
+
+use crate::placement::ImageExtent;
+use crate::{Crop, Optimizer, SixelEvent};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tile {
+    /// The cell column this tile covers, relative to the top left cell of the image.
+    pub column: usize,
+    /// The cell row this tile covers, relative to the top left cell of the image.
+    pub row: usize,
+    pub events: Vec<SixelEvent>,
+}
+
+impl Tile {
+    pub fn write_to(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        for event in &self.events {
+            event.write_to(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Tiler {
+    cell_width: usize,
+    cell_height: usize,
+    image: Vec<SixelEvent>,
+    in_image: bool,
+}
+
+impl Tiler {
+    /// Creates a tiler for cells of the given size in sixel pixels.
+    pub fn new(cell_width: usize, cell_height: usize) -> Self {
+        Tiler {
+            cell_width: std::cmp::max(cell_width, 1),
+            cell_height: std::cmp::max(cell_height, 1),
+            image: vec![],
+            in_image: false,
+        }
+    }
+    /// Feeds a single event to the tiler. Once an image ends, its tiles are passed to the callback
+    /// row by row. Events outside of images are ignored.
+    pub fn advance(&mut self, event: SixelEvent, mut cb: impl FnMut(Tile)) {
+        match event {
+            SixelEvent::Dcs { .. } => {
+                self.finish(&mut cb);
+                self.in_image = true;
+                self.image.push(event);
+            }
+            SixelEvent::End | SixelEvent::Unterminated if self.in_image => {
+                self.image.push(event);
+                self.finish(&mut cb);
+            }
+            _ if self.in_image => self.image.push(event),
+            _ => {}
+        }
+    }
+    /// Tiles an image that was not terminated by an `End` event.
+    pub fn finish(&mut self, mut cb: impl FnMut(Tile)) {
+        if !self.in_image {
+            return;
+        }
+        let image = std::mem::take(&mut self.image);
+        let (columns, rows) =
+            ImageExtent::from_events(&image).cells(self.cell_width, self.cell_height);
+        for row in 0..rows {
+            let mut crop = Crop::new(
+                0,
+                row * self.cell_height,
+                columns * self.cell_width,
+                self.cell_height,
+            );
+            let mut splitter = RowSplitter::new(self.cell_width, self.cell_height, columns);
+            for event in &image {
+                crop.advance(*event, |event| splitter.advance(event));
+            }
+            crop.finish(|event| splitter.advance(event));
+            for (column, events) in splitter.finish().into_iter().enumerate() {
+                cb(Tile {
+                    column,
+                    row,
+                    events,
+                });
+            }
+        }
+        self.in_image = false;
+    }
+}
+
+// Splits a row of tiles, as cropped out of the source image, into its tiles. Every sixel data
+// event is cut at the cell boundaries and only given to the tiles it covers, the other events are
+// given to every tile.
+struct RowSplitter {
+    cell_width: usize,
+    tiles: Vec<TileWriter>,
+    column: usize,
+}
+
+impl RowSplitter {
+    fn new(cell_width: usize, cell_height: usize, columns: usize) -> Self {
+        RowSplitter {
+            cell_width,
+            tiles: (0..columns)
+                .map(|_| TileWriter::new(cell_width, cell_height))
+                .collect(),
+            column: 0,
+        }
+    }
+    fn advance(&mut self, event: SixelEvent) {
+        match event {
+            SixelEvent::RasterAttribute { pan, pad, ph, pv } => {
+                for (column, tile) in self.tiles.iter_mut().enumerate() {
+                    let x = column * self.cell_width;
+                    tile.advance(SixelEvent::RasterAttribute {
+                        pan,
+                        pad,
+                        ph: ph.map(|ph| ph.saturating_sub(x)),
+                        pv,
+                    });
+                }
+            }
+            SixelEvent::Data { byte } => self.split(byte, 1, false),
+            SixelEvent::Repeat {
+                repeat_count,
+                byte_to_repeat,
+            } => self.split(byte_to_repeat, repeat_count, true),
+            _ => {
+                if let SixelEvent::GotoBeginningOfLine | SixelEvent::GotoNextLine = event {
+                    self.column = 0;
+                }
+                for tile in &mut self.tiles {
+                    tile.advance(event);
+                }
+            }
+        }
+    }
+    fn split(&mut self, byte: u8, count: usize, is_repeat: bool) {
+        let start = self.column;
+        let end = start.saturating_add(count);
+        self.column = end;
+        let first_tile = start / self.cell_width;
+        let last_tile = std::cmp::min(end.div_ceil(self.cell_width), self.tiles.len());
+        for tile in first_tile..last_tile {
+            let tile_start = tile * self.cell_width;
+            let count =
+                std::cmp::min(end, tile_start + self.cell_width) - std::cmp::max(start, tile_start);
+            let event = if is_repeat {
+                SixelEvent::Repeat {
+                    repeat_count: count,
+                    byte_to_repeat: byte,
+                }
+            } else {
+                SixelEvent::Data { byte }
+            };
+            self.tiles[tile].advance(event);
+        }
+    }
+    fn finish(self) -> Vec<Vec<SixelEvent>> {
+        self.tiles.into_iter().map(TileWriter::finish).collect()
+    }
+}
+
+// The events of one tile, relative to its top left corner.
+struct TileWriter {
+    crop: Crop,
+    optimizer: Optimizer,
+    events: Vec<SixelEvent>,
+}
+
+impl TileWriter {
+    fn new(cell_width: usize, cell_height: usize) -> Self {
+        TileWriter {
+            crop: Crop::new(0, 0, cell_width, cell_height),
+            optimizer: Optimizer::new(),
+            events: vec![],
+        }
+    }
+    fn advance(&mut self, event: SixelEvent) {
+        let (optimizer, events) = (&mut self.optimizer, &mut self.events);
+        self.crop.advance(event, |event| {
+            optimizer.advance(event, |event| events.push(event))
+        });
+    }
+    fn finish(mut self) -> Vec<SixelEvent> {
+        let (optimizer, events) = (&mut self.optimizer, &mut self.events);
+        self.crop
+            .finish(|event| optimizer.advance(event, |event| events.push(event)));
+        optimizer.finish(|event| events.push(event));
+        self.events
+    }
+}
+
+#[cfg(test)]
+#[path = "./tiler_tests.rs"]
+mod tests;
@@ -0,0 +1,98 @@
+use crate::{Parser, SixelEvent, Tile, Tiler};
+
+fn tile(sample: &str, cell_width: usize, cell_height: usize) -> Vec<(usize, usize, String)> {
+    let mut parser = Parser::new();
+    let mut tiler = Tiler::new(cell_width, cell_height);
+    let mut tiles = vec![];
+    for byte in sample.as_bytes() {
+        parser.advance(byte, |sixel_event| {
+            tiler.advance(sixel_event, |tile| tiles.push(tile))
+        });
+    }
+    tiler.finish(|tile| tiles.push(tile));
+    tiles
+        .into_iter()
+        .map(|tile| {
+            let mut serialized = vec![];
+            tile.write_to(&mut serialized).unwrap();
+            (
+                tile.column,
+                tile.row,
+                String::from_utf8(serialized).unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn tiles_have_their_own_palette() {
+    let sample = "
+        \u{1b}Pq\"1;1;4;12
+        #1;2;100;0;0#2;2;0;100;0
+        #1~~#2~~-
+        #2~~#1~~
+        \u{1b}\\
+    ";
+    let expected = vec![
+        (0, 0, "\u{1b}Pq\"1;1;2;6#1;2;100;0;0~~\u{1b}\\".to_owned()),
+        (1, 0, "\u{1b}Pq\"1;1;2;6#2;2;0;100;0~~\u{1b}\\".to_owned()),
+        (0, 1, "\u{1b}Pq\"1;1;2;6#2;2;0;100;0~~\u{1b}\\".to_owned()),
+        (1, 1, "\u{1b}Pq\"1;1;2;6#1;2;100;0;0~~\u{1b}\\".to_owned()),
+    ];
+    assert_eq!(tile(sample, 2, 6), expected);
+}
+
+#[test]
+fn cells_that_are_not_aligned_to_bands() {
+    let sample = "\u{1b}P0;1q#1;2;100;0;0#1~~\u{1b}\\";
+    let expected = vec![
+        (0, 0, "\u{1b}P0;1q#1;2;100;0;0NN\u{1b}\\".to_owned()),
+        (0, 1, "\u{1b}P0;1q#1;2;100;0;0BB\u{1b}\\".to_owned()),
+    ];
+    assert_eq!(tile(sample, 2, 4), expected);
+}
+
+#[test]
+fn runs_are_split_at_cell_boundaries() {
+    let sample = "\u{1b}P0;1q#1;2;100;0;0#2;2;0;100;0#1!7~$#2???!4@\u{1b}\\";
+    let expected = vec![
+        (0, 0, "\u{1b}P0;1q#1;2;100;0;0~~~\u{1b}\\".to_owned()),
+        (
+            1,
+            0,
+            "\u{1b}P0;1q#1;2;100;0;0#2;2;0;100;0#1~~~$#2@@@\u{1b}\\".to_owned(),
+        ),
+        (
+            2,
+            0,
+            "\u{1b}P0;1q#1;2;100;0;0#2;2;0;100;0#1~$#2@\u{1b}\\".to_owned(),
+        ),
+    ];
+    assert_eq!(tile(sample, 3, 6), expected);
+}
+
+#[test]
+fn image_size_includes_data_outside_of_the_raster_attributes() {
+    let sample = "\u{1b}P0;1q\"1;1;1;1#1;2;100;0;0#1@@@\u{1b}\\";
+    let tiles = tile(sample, 1, 1);
+    let coordinates: Vec<(usize, usize)> = tiles.iter().map(|(c, r, _)| (*c, *r)).collect();
+    assert_eq!(coordinates, vec![(0, 0), (1, 0), (2, 0)]);
+}
+
+#[test]
+fn trailing_blank_sixels_dont_add_tiles() {
+    let sample = "\u{1b}P0;1q#1;2;100;0;0#1@@!5?-??\u{1b}\\";
+    let tiles = tile(sample, 1, 6);
+    let coordinates: Vec<(usize, usize)> = tiles.iter().map(|(c, r, _)| (*c, *r)).collect();
+    assert_eq!(coordinates, vec![(0, 0), (1, 0)]);
+}
+
+#[test]
+fn events_outside_of_images_are_ignored() {
+    let mut tiler = Tiler::new(10, 20);
+    let mut tiles: Vec<Tile> = vec![];
+    tiler.advance(SixelEvent::Data { byte: b'~' }, |tile| tiles.push(tile));
+    tiler.advance(SixelEvent::End, |tile| tiles.push(tile));
+    tiler.finish(|tile| tiles.push(tile));
+    assert_eq!(tiles, vec![]);
+}
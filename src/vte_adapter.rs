@@ -0,0 +1,103 @@
+//! Integration with the [`vte`](::vte) crate.
+//!
+//! `vte` parses the DCS introducer itself and hands its content to `Perform::hook`, `put` and
+//! `unhook`. [`VteAdapter`] turns these calls into [`SixelEvent`]s, so an emulator's `Perform`
+//! implementation can delegate to it.
+//!
+//! # Example
+//! ```rust
+//! use sixel_tokenizer::{SixelEvent, VteAdapter};
+//! use vte::{Params, Perform};
+//!
+//! #[derive(Default)]
+//! struct Terminal {
+//!     sixel: VteAdapter,
+//!     sixel_events: Vec<SixelEvent>,
+//! }
+//!
+//! impl Perform for Terminal {
+//!     fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+//!         self.sixel.hook(params, intermediates, ignore, action);
+//!     }
+//!     fn put(&mut self, byte: u8) {
+//!         let events = &mut self.sixel_events;
+//!         self.sixel.put(byte, |event| events.push(event));
+//!     }
+//!     fn unhook(&mut self) {
+//!         let events = &mut self.sixel_events;
+//!         self.sixel.unhook(|event| events.push(event));
+//!     }
+//! }
+//!
+//! let mut terminal = Terminal::default();
+//! let mut parser = vte::Parser::new();
+//! for byte in b"\x1bPq#1~~\x1b\\" {
+//!     parser.advance(&mut terminal, *byte);
+//! }
+//! assert_eq!(terminal.sixel_events.len(), 5);
+//! assert_eq!(terminal.sixel_events[4], SixelEvent::End);
+//! ```
+
+use ::vte::Params;
+
+use crate::{Parser, SixelEvent};
+
+#[derive(Clone, Debug, Default)]
+pub struct VteAdapter {
+    // only present between the `hook` and `unhook` of a sixel DCS
+    parser: Option<Parser>,
+}
+
+impl VteAdapter {
+    pub fn new() -> Self {
+        VteAdapter::default()
+    }
+    /// Starts a sixel image if this is a sixel DCS (`ESC P <params> q`). Returns false for other
+    /// device control strings, whose `put` and `unhook` calls will be ignored.
+    ///
+    /// The `Dcs` event is emitted along with the first event of the image. Since `vte` reports
+    /// empty parameters as 0, so does the `Dcs` event.
+    pub fn hook(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        action: char,
+    ) -> bool {
+        if action != 'q' || !intermediates.is_empty() || ignore {
+            self.parser = None;
+            return false;
+        }
+        let mut params = params
+            .iter()
+            .map(|param| param.first().copied().unwrap_or(0));
+        let macro_parameter = params.next().and_then(|p| u8::try_from(p).ok());
+        let transparent_background = params.next().and_then(|p| u8::try_from(p).ok());
+        let horizontal_pixel_distance = params.next().map(usize::from);
+        self.parser = Some(Parser::new_in_body(
+            macro_parameter,
+            transparent_background,
+            horizontal_pixel_distance,
+        ));
+        true
+    }
+    pub fn put(&mut self, byte: u8, cb: impl FnMut(SixelEvent)) {
+        if let Some(parser) = self.parser.as_mut() {
+            parser.advance(&byte, cb);
+        }
+    }
+    /// Ends the current sixel image, emitting any pending event and then `End`.
+    pub fn unhook(&mut self, cb: impl FnMut(SixelEvent)) {
+        if let Some(mut parser) = self.parser.take() {
+            parser.finish(cb);
+        }
+    }
+    /// Whether we are between the `hook` and `unhook` of a sixel DCS.
+    pub fn is_active(&self) -> bool {
+        self.parser.is_some()
+    }
+}
+
+#[cfg(test)]
+#[path = "./vte_adapter_tests.rs"]
+mod tests;
@@ -0,0 +1,84 @@
+use ::vte::{Params, Perform};
+
+use crate::{SixelEvent, VteAdapter};
+
+#[derive(Default)]
+struct Recorder {
+    adapter: VteAdapter,
+    sixel_events: Vec<SixelEvent>,
+    printed: String,
+}
+
+impl Perform for Recorder {
+    fn print(&mut self, c: char) {
+        self.printed.push(c);
+    }
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.adapter.hook(params, intermediates, ignore, action);
+    }
+    fn put(&mut self, byte: u8) {
+        let events = &mut self.sixel_events;
+        self.adapter.put(byte, |event| events.push(event));
+    }
+    fn unhook(&mut self) {
+        let events = &mut self.sixel_events;
+        self.adapter.unhook(|event| events.push(event));
+    }
+}
+
+fn run(sample: &[u8]) -> Recorder {
+    let mut recorder = Recorder::default();
+    let mut parser = ::vte::Parser::new();
+    for byte in sample {
+        parser.advance(&mut recorder, *byte);
+    }
+    recorder
+}
+
+#[test]
+fn sixel_through_vte() {
+    let recorder = run(b"a\x1bP0;1;5q\"1;1;2;6#1;2;100;0;0#1~!2@$-\x1b\\b");
+    let expected = vec![
+        SixelEvent::new_dcs(Some(0), Some(1), Some(5)),
+        SixelEvent::new_raster(1, 1, Some(2), Some(6)).unwrap(),
+        SixelEvent::new_color_introducer(1, Some(2), Some(100), Some(0), Some(0)).unwrap(),
+        SixelEvent::new_color_introducer(1, None, None, None, None).unwrap(),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::new_repeat(2, b'@').unwrap(),
+        SixelEvent::GotoBeginningOfLine,
+        SixelEvent::GotoNextLine,
+        SixelEvent::End,
+    ];
+    assert_eq!(recorder.sixel_events, expected);
+    assert_eq!(recorder.printed, "ab");
+    assert!(!recorder.adapter.is_active());
+}
+
+#[test]
+fn dcs_without_parameters() {
+    let recorder = run(b"\x1bPq~\x1b\\");
+    let expected = vec![
+        // vte does not distinguish a missing parameter from a 0
+        SixelEvent::new_dcs(Some(0), None, None),
+        SixelEvent::Data { byte: b'~' },
+        SixelEvent::End,
+    ];
+    assert_eq!(recorder.sixel_events, expected);
+}
+
+#[test]
+fn pending_event_is_emitted_on_unhook() {
+    let recorder = run(b"\x1bP1q#1;2;0;100;0\x1b\\");
+    let expected = vec![
+        SixelEvent::new_dcs(Some(1), None, None),
+        SixelEvent::new_color_introducer(1, Some(2), Some(0), Some(100), Some(0)).unwrap(),
+        SixelEvent::End,
+    ];
+    assert_eq!(recorder.sixel_events, expected);
+}
+
+#[test]
+fn other_device_control_strings_are_ignored() {
+    let recorder = run(b"\x1bP$qm\x1b\\\x1bP1000p~~\x1b\\");
+    assert_eq!(recorder.sixel_events, vec![]);
+}